@@ -2,6 +2,7 @@ import * as vscode from 'vscode';
 import { StepIndexManager } from './indexer';
 import { resolveRunCommand } from './run_matrix';
 import { shellQuote } from './utils';
+import { definitionTarget } from './providers/shared';
 
 function extractScenarioName(doc: vscode.TextDocument, line: number): string | undefined {
   for (let i = line; i >= 0; i--) {
@@ -73,11 +74,12 @@ export function registerCommands(
       }
       if (!chosen) return;
       const uri = vscode.Uri.joinPath(root, chosen.s.file);
-      const pos = new vscode.Position(Math.max(0, (chosen.s.line ?? 1) - 1), 0);
+      const target = definitionTarget(chosen.s);
+      const sel = target.targetSelectionRange ?? target.targetRange;
       const doc = await vscode.workspace.openTextDocument(uri);
       const ed = await vscode.window.showTextDocument(doc);
-      ed.selection = new vscode.Selection(pos, pos);
-      ed.revealRange(new vscode.Range(pos, pos), vscode.TextEditorRevealType.InCenter);
+      ed.selection = new vscode.Selection(sel.start, sel.end);
+      ed.revealRange(sel, vscode.TextEditorRevealType.InCenter);
     } catch (e) {
       vscode.window.showErrorMessage(`CukeRust: Failed to open step: ${String(e)}`);
     }
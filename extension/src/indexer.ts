@@ -1,5 +1,5 @@
 import * as vscode from 'vscode';
-import type { StepEntry, StepIndex, SourceFileInput } from './types';
+import type { DetectorConfig, LoadedArtifact, StepEntry, StepIndex, StepIndexDelta, SourceFileInput } from './types';
 import { exec } from 'child_process';
 import { dedupeSteps } from './core/dedupe';
 
@@ -11,6 +11,8 @@ export class StepIndexManager {
   private artifactStale = new Map<string, boolean>();
   private ambiguityMemory = new Map<string, { file: string; line: number }>();
   private lastBuildMs = new Map<string, number>();
+  private stepIndexers = new Map<string, any>(); // key: workspace folder fsPath, static scan only
+  private scannedFiles = new Map<string, Set<string>>(); // relative paths the static scan included
   private forceStatic = false;
 
   constructor(private context: vscode.ExtensionContext) {
@@ -67,6 +69,9 @@ export class StepIndexManager {
   }
 
   async rebuildForFolder(folder: vscode.WorkspaceFolder): Promise<void> {
+    this.stepIndexers.get(folder.uri.fsPath)?.free?.();
+    this.stepIndexers.delete(folder.uri.fsPath);
+    this.scannedFiles.delete(folder.uri.fsPath);
     const mode = vscode.workspace.getConfiguration('cukerust', folder).get<'auto'|'static-scan'|'artifact'|'runtime-list'>('discovery.mode', 'auto');
     if (mode === 'artifact' || mode === 'auto') {
       const loaded = await this.tryLoadArtifact(folder);
@@ -107,10 +112,15 @@ export class StepIndexManager {
         // ignore read failures
       }
     }
-    const payload = JSON.stringify({ files: inputs });
+    const detectors = vscode.workspace.getConfiguration('cukerust', folder).get<DetectorConfig>('detectors', {});
     let result: string;
     try {
-      result = wasm.extract_step_index(payload);
+      // Kept per folder so Rust file changes only re-extract what they affect
+      const indexer = new wasm.StepIndexer(JSON.stringify({ detectors }));
+      indexer.upsert_files(JSON.stringify({ files: inputs }));
+      result = indexer.snapshot();
+      this.stepIndexers.set(folder.uri.fsPath, indexer);
+      this.scannedFiles.set(folder.uri.fsPath, new Set(inputs.map((i) => i.path)));
     } catch (e) {
       vscode.window.showErrorMessage(`CukeRust: extract_step_index failed: ${String(e)}`);
       return;
@@ -136,7 +146,7 @@ export class StepIndexManager {
       const stat = await vscode.workspace.fs.stat(artifact);
       const bytes = await vscode.workspace.fs.readFile(artifact);
       const text = new TextDecoder('utf-8').decode(bytes);
-      const index: StepIndex = JSON.parse(text);
+      const index = await this.parseArtifact(text, cfgPath);
       const fresh = await this.isArtifactFresh(folder, artifact, stat.mtime, index);
       if (!fresh) {
         // Mark stale; fall back to static scan
@@ -152,6 +162,21 @@ export class StepIndexManager {
     }
   }
 
+  /** Loads an artifact best-effort with the WASM loader, warning about the entries it left out. */
+  private async parseArtifact(text: string, cfgPath: string): Promise<StepIndex> {
+    const wasm = await this.ensureWasm().catch(() => null);
+    if (!wasm?.load_step_index) return JSON.parse(text) as StepIndex;
+    const loaded = JSON.parse(wasm.load_step_index(text)) as LoadedArtifact & { error?: string };
+    if (loaded.error) throw new Error(loaded.error);
+    const { rejected } = loaded;
+    if (rejected.length > 0) {
+      const first = rejected[0];
+      const entries = rejected.length === 1 ? 'entry' : 'entries';
+      vscode.window.showWarningMessage(`CukeRust: skipped ${rejected.length} invalid ${entries} of ${cfgPath} (${first.pointer}: ${first.reason})`);
+    }
+    return loaded.index;
+  }
+
   private async isArtifactFresh(folder: vscode.WorkspaceFolder, artifact: vscode.Uri, artifactMtime: number, index: StepIndex): Promise<boolean> {
     // If stats.generated_at present, prefer that; else compare artifact mtime with referenced files
     try {
@@ -197,14 +222,15 @@ export class StepIndexManager {
           match_mode: cfg.get<'anchored'|'smart'|'substring'>('regex.matchMode', 'smart'),
         },
         steps: index.steps,
+        parameters: index.parameters ?? [],
       });
       const out = wasm.diagnostics_for_feature(payload) as string;
-      let parsed: { diags?: Array<{ line: number; message: string; severity: string }> } = {};
+      let parsed: { diags?: Array<{ line: number; message: string; severity: string; start_col?: number; end_col?: number }> } = {};
       try { parsed = JSON.parse(out); } catch {}
       const diags: vscode.Diagnostic[] = [];
       for (const d of parsed.diags ?? []) {
         const lineText = d.line < doc.lineCount ? doc.lineAt(d.line).text : '';
-        const range = new vscode.Range(d.line, 0, d.line, lineText.length);
+        const range = new vscode.Range(d.line, d.start_col ?? 0, d.line, d.end_col ?? lineText.length);
         const sev = vscode.DiagnosticSeverity.Warning;
         const vd = new vscode.Diagnostic(range, d.message, sev);
         vd.source = 'CukeRust';
@@ -275,29 +301,72 @@ export class StepIndexManager {
     return this.ambiguityMemory.size > 0;
   }
 
-  // Watchers to debounce rebuilds on Rust file changes
+  // Applies a changed or deleted Rust file to a static-scan index; false when the folder
+  // needs a rebuild instead (no incremental indexer in artifact/runtime-list modes).
+  // New files always go through a rebuild, which applies the ignore globs.
+  private async applyFileChange(folder: vscode.WorkspaceFolder, uri: vscode.Uri, deleted: boolean): Promise<boolean> {
+    const indexer = this.stepIndexers.get(folder.uri.fsPath);
+    const index = this.indexes.get(folder.uri.fsPath);
+    const scanned = this.scannedFiles.get(folder.uri.fsPath);
+    if (!indexer || !index || !scanned) return false;
+    const rel = vscode.workspace.asRelativePath(uri, false);
+    if (!scanned.has(rel)) return true; // excluded from the scan (target/, ignore globs)
+    let out: string;
+    if (deleted) {
+      scanned.delete(rel);
+      out = indexer.remove_file(rel);
+    } else {
+      try {
+        const text = new TextDecoder('utf-8').decode(await vscode.workspace.fs.readFile(uri));
+        out = indexer.upsert_file(JSON.stringify({ path: rel, text }));
+      } catch {
+        return false;
+      }
+    }
+    const delta: StepIndexDelta = JSON.parse(out);
+    if ((delta as any).error) return false;
+    const touched = new Set([...delta.added, ...delta.removed, ...delta.changed].map((s) => s.file));
+    if (touched.size > 0) {
+      const kept = index.steps.filter((s) => !touched.has(s.file));
+      const fresh = [...touched].flatMap((file) => JSON.parse(indexer.file_steps(file)) as StepEntry[]);
+      const steps = [...kept, ...fresh].sort((a, b) => (a.file < b.file ? -1 : a.file > b.file ? 1 : a.line - b.line));
+      index.steps = dedupeSteps(steps);
+    }
+    index.stats = delta.stats;
+    index.warnings = JSON.parse(indexer.warnings());
+    index.parameters = JSON.parse(indexer.parameters());
+    return true;
+  }
+
+  // Watchers to update the index on Rust file changes: incrementally for static scans,
+  // otherwise by a debounced rebuild
   initWatchers() {
     const folders = vscode.workspace.workspaceFolders ?? [];
     for (const f of folders) {
       const pattern = new vscode.RelativePattern(f, '**/*.rs');
       const watcher = vscode.workspace.createFileSystemWatcher(pattern);
+      const refresh = () => {
+        for (const doc of vscode.workspace.textDocuments) {
+          if (vscode.workspace.getWorkspaceFolder(doc.uri)?.uri.fsPath === f.uri.fsPath) {
+            this.refreshDiagnostics(doc);
+          }
+        }
+      };
+      const onChange = (deleted: boolean) => async (uri: vscode.Uri) => {
+        if (await this.applyFileChange(f, uri, deleted)) refresh();
+        else schedule();
+      };
       const schedule = () => {
         const key = f.uri.fsPath;
         const prev = this.rebuildTimers.get(key);
         if (prev) clearTimeout(prev);
-        const t = setTimeout(() => { this.rebuildForFolder(f).then(() => {
-          // refresh diagnostics for open feature docs under this folder
-          for (const doc of vscode.workspace.textDocuments) {
-            if (vscode.workspace.getWorkspaceFolder(doc.uri)?.uri.fsPath === key) {
-              this.refreshDiagnostics(doc);
-            }
-          }
-        }); }, 500);
+        // refresh diagnostics for open feature docs under this folder
+        const t = setTimeout(() => { this.rebuildForFolder(f).then(refresh); }, 500);
         this.rebuildTimers.set(key, t);
       };
       watcher.onDidCreate(schedule, this, this.context.subscriptions);
-      watcher.onDidChange(schedule, this, this.context.subscriptions);
-      watcher.onDidDelete(schedule, this, this.context.subscriptions);
+      watcher.onDidChange(onChange(false), this, this.context.subscriptions);
+      watcher.onDidDelete(onChange(true), this, this.context.subscriptions);
       this.context.subscriptions.push(watcher);
     }
   }
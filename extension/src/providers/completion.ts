@@ -22,6 +22,7 @@ export function registerCompletionProvider(
           const ci = new vscode.CompletionItem(label, vscode.CompletionItemKind.Snippet);
           ci.insertText = new vscode.SnippetString(toSnippet(s.regex));
           ci.detail = `${s.file}:${s.line}`;
+          if (s.notes) ci.documentation = new vscode.MarkdownString(s.notes);
           items.push(ci);
         }
         return items;
@@ -1,7 +1,7 @@
 import * as vscode from 'vscode';
 import { StepIndexManager } from '../indexer';
 import { detectDialect, getDialect, buildStepKeywordRegex, extractOutlineContext, resolvePlaceholders } from '../gherkin';
-import { bodyRange, featureSelector as ds } from './shared';
+import { bodyRange, definitionTarget, featureSelector as ds } from './shared';
 
 export function registerDefinitionProvider(
   context: vscode.ExtensionContext,
@@ -72,11 +72,10 @@ export function registerDefinitionProvider(
       const links: vscode.LocationLink[] = matches.map((s) => {
         const root = folder?.uri ?? vscode.Uri.file('/');
         const targetUri = vscode.Uri.joinPath(root, s.file);
-        const pos = new vscode.Position(Math.max(0, (s.line ?? 1) - 1), 0);
         return {
           originSelectionRange: origin,
           targetUri,
-          targetRange: new vscode.Range(pos, pos),
+          ...definitionTarget(s),
         } satisfies vscode.LocationLink;
       });
       if (links.length === 1) return links;
@@ -88,11 +87,10 @@ export function registerDefinitionProvider(
         manager.setAmbiguityChoice(`${kind}|${body}`, { file: s.file, line: s.line });
         const root = folder?.uri ?? vscode.Uri.file('/');
         const targetUri = vscode.Uri.joinPath(root, s.file);
-        const pos = new vscode.Position(Math.max(0, (s.line ?? 1) - 1), 0);
         const link: vscode.LocationLink = {
           originSelectionRange: origin,
           targetUri,
-          targetRange: new vscode.Range(pos, pos),
+          ...definitionTarget(s),
         };
         return [link];
       }
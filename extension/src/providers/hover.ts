@@ -41,6 +41,13 @@ export function registerHoverProvider(
             const lineNum = Math.max(0, (s.line ?? 1) - 1);
             const openCmd = `command:vscode.open?${encodeURIComponent(JSON.stringify([target.with({ fragment: `L${lineNum + 1}` })]))}`;
             md.appendMarkdown(`Source: [${s.file}:${s.line}](${openCmd})\n`);
+            const args = [s.expects_table && 'DataTable', s.expects_docstring && 'DocString'].filter(Boolean);
+            if (args.length) {
+              md.appendMarkdown(`Expects: ${args.join(', ')}\n`);
+            }
+            if (s.notes) {
+              md.appendMarkdown(`\n${s.notes}\n\n`);
+            }
             if (b !== body) {
               md.appendMarkdown(`Resolved: \`${b}\`\n`);
             }
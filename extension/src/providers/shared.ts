@@ -1,4 +1,5 @@
 import * as vscode from 'vscode';
+import type { SourceSpan, StepEntry } from '../types';
 
 export function bodyRange(lineText: string, keyword: string, body: string, line: number): vscode.Range {
   const esc = keyword.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
@@ -13,3 +14,17 @@ export const featureSelector: vscode.DocumentSelector = [
   { language: 'cucumber' as any },
   { pattern: '**/*.feature' },
 ];
+
+function toRange(span: SourceSpan): vscode.Range {
+  return new vscode.Range(span.start_line - 1, span.start_col, span.end_line - 1, span.end_col);
+}
+
+/** Target of a step definition: its whole span, selecting the function name (else the pattern). */
+export function definitionTarget(s: StepEntry): { targetRange: vscode.Range; targetSelectionRange?: vscode.Range } {
+  const selection = s.function_span ?? s.pattern_span;
+  if (s.span) {
+    return { targetRange: toRange(s.span), targetSelectionRange: selection ? toRange(selection) : undefined };
+  }
+  const pos = new vscode.Position(Math.max(0, (s.line ?? 1) - 1), 0);
+  return { targetRange: new vscode.Range(pos, pos) };
+}
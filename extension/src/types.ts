@@ -1,29 +1,135 @@
 export type StepKind = 'Given' | 'When' | 'Then';
 
+export interface StepCapture {
+  index: number; // 1-based group index
+  name?: string;
+  type?: string;
+  start: number; // byte span of the group in `regex`
+  end: number;
+}
+
 export interface StepEntry {
   kind: StepKind;
   regex: string;
+  pattern_kind?: 'Regex' | 'Literal' | 'Expression';
   file: string; // relative path
   line: number; // 1-based
-  function?: string;
-  captures?: string[];
+  function?: string; // bare name
+  module?: string; // e.g. crate::steps::account
+  captures?: StepCapture[];
   tags?: string[];
   notes?: string;
+  detector?: 'Attribute' | 'Macro' | 'Builder'; // absent for custom detectors
+  unresolved?: string;
+  invalid?: string; // compile error of the pattern
+  span?: SourceSpan; // whole definition
+  pattern_span?: SourceSpan;
+  function_span?: SourceSpan;
+  expects_table?: boolean; // absent when unknown
+  expects_docstring?: boolean;
+}
+
+export interface SourceSpan {
+  start: number; // byte offsets, end exclusive
+  end: number;
+  start_line: number; // 1-based
+  start_col: number; // 0-based, UTF-16 code units
+  end_line: number;
+  end_col: number;
 }
 
 export interface StepIndexStats {
   total: number;
   by_kind: { Given: number; When: number; Then: number } | { given: number; when: number; then: number };
   ambiguous: number;
+  ambiguities?: StepAmbiguity[];
+  by_file?: Record<string, { steps: number; ambiguous: number }>;
+  by_detector?: { Attribute: number; Macro: number; Builder: number; Other: number };
+  overlaps?: StepOverlap[]; // only when requested
   generated_at?: string;
 }
 
+/** Definitions sharing a kind and pattern. */
+export interface StepAmbiguity {
+  kind: StepKind;
+  regex: string;
+  steps: StepLocation[];
+}
+
+/** Same-kind definitions with different patterns that all match `witness`. */
+export interface StepOverlap {
+  kind: StepKind;
+  witness: string;
+  steps: StepLocation[];
+}
+
+export interface StepLocation {
+  regex: string;
+  file: string; // relative path
+  line: number; // 1-based
+  function?: string;
+}
+
+export interface StepWarning {
+  kind: 'CaptureCountMismatch' | 'UnterminatedLiteral' | 'UnsupportedPattern' | 'MalformedDefinition' | 'InvalidPattern' | 'UnknownStepCrate';
+  file: string; // relative path
+  line: number; // 1-based
+  function?: string;
+  detector?: 'Attribute' | 'Macro' | 'Builder';
+  message: string;
+}
+
+export interface CustomParameter {
+  name: string; // Cucumber Expression parameter name
+  type_name: string; // Rust type deriving `Parameter`
+  regex: string;
+  file: string;
+  line: number;
+}
+
 export interface StepIndex {
+  schema_version?: number; // absent in version 1 artifacts
   steps: StepEntry[];
   stats: StepIndexStats;
+  warnings?: StepWarning[];
+  parameters?: CustomParameter[];
+}
+
+/** Result of the WASM `load_step_index`: the entries that loaded and those left out. */
+export interface LoadedArtifact {
+  index: StepIndex;
+  from_version: number; // schema_version before migration
+  rejected: RejectedEntry[];
+}
+
+export interface RejectedEntry {
+  pointer: string; // JSON pointer, e.g. /steps/3
+  reason: string;
+}
+
+/** What a `StepIndexer` update changed; `stats` are those of the whole index after it. */
+export interface StepIndexDelta {
+  added: StepEntry[];
+  removed: StepEntry[];
+  changed: StepEntry[];
+  stats: StepIndexStats;
 }
 
 export interface SourceFileInput {
   path: string;
   text: string;
 }
+
+export interface DetectorName {
+  name: string;
+  /** Omitted when the first argument names the kind, as in `step!(Given, "...")`. */
+  kind?: 'Given' | 'When' | 'Then';
+}
+
+export interface DetectorConfig {
+  macros?: DetectorName[];
+  attributes?: DetectorName[];
+  methods?: DetectorName[];
+  /** Crates besides cucumber that export step attributes or macros. */
+  crates?: string[];
+}
@@ -0,0 +1,33 @@
+Feature: Cucumber Expressions compile into matchers
+
+  Scenario Outline: Expression matches step text
+    Then the expression "<expression>" matches "<text>"
+
+    Examples:
+      | expression                     | text                         |
+      | I have {int} cukes             | I have 42 cukes              |
+      | I have {int} cukes             | I have -3 cukes              |
+      | it weighs {float} kg           | it weighs 1.25 kg            |
+      | I am {word}                    | I am hungry                  |
+      | I say {string}                 | I say "hello world"          |
+      | anything {} goes               | anything at all goes         |
+      | I have {int} cuke(s)           | I have 1 cuke                |
+      | I have {int} cuke(s)           | I have 2 cukes               |
+      | it is in my belly/stomach      | it is in my stomach          |
+      | a \(literal\) paren            | a (literal) paren            |
+      | a dot. a question?             | a dot. a question?           |
+
+  Scenario Outline: Expression rejects step text
+    Then the expression "<expression>" does not match "<text>"
+
+    Examples:
+      | expression                     | text                         |
+      | I have {int} cukes             | I have many cukes            |
+      | I am {word}                    | I am very hungry             |
+      | it is in my belly/stomach      | it is in my bellystomach     |
+      | I have {int} cukes             | so I have 5 cukes now        |
+      | a dot. a question?             | a dotx a question            |
+
+  Scenario: Invalid expressions report an error
+    Then the expression "I have {int cukes" fails to compile
+    And the expression "I have (s cukes" fails to compile
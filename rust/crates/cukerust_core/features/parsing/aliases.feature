@@ -0,0 +1,112 @@
+Feature: Renamed and re-exported step attributes and macros
+
+  Scenario: `use ... as` renames of cucumber's attributes and macros are steps
+    Given a Rust file "src/steps.rs" with content:
+      """
+      use cucumber::given as dado;
+      use cucumber::{when as on, then as entonces};
+
+      #[dado(regex = r"^tengo (\d+) pepinos$")]
+      fn tengo(world: &mut World, n: usize) {}
+
+      #[on("I eat")]
+      fn eat(world: &mut World) {}
+
+      entonces!(r"^listo$", || {});
+      """
+    When we extract the Step Index
+    Then the index contains 3 steps
+    And there is a step with kind "Given" and regex "^tengo (\d+) pepinos$" from file "src/steps.rs" at line 5 with function "tengo"
+    And there is a step with kind "When" and regex "I eat" from file "src/steps.rs" at line 8 with function "eat"
+    And there is a step with kind "Then" and regex "^listo$" from file "src/steps.rs" at line 11
+
+  Scenario: Re-exports through the crate's own modules are followed
+    Given a Rust file "src/steps.rs" with content:
+      """
+      use crate::{given, when as cuando};
+
+      #[given("a re-exported attribute")]
+      fn reexported(world: &mut World) {}
+
+      #[cuando("a renamed re-export")]
+      fn renamed(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is a step with kind "When" and regex "a renamed re-export" from file "src/steps.rs" at line 7 with function "renamed"
+
+  Scenario: Step names imported from unrelated crates are not steps
+    Given a Rust file "src/props.rs" with content:
+      """
+      use proptest_given::given;
+      use other::helper as when;
+
+      #[given("not a cucumber step")]
+      fn prop() {}
+
+      when!("^not a step either$", || {});
+
+      #[cucumber::then("qualified paths still count")]
+      fn qualified(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "Then" and regex "qualified paths still count" from file "src/props.rs" at line 10 with function "qualified"
+
+  Scenario: Step names imported from unknown crates are reported
+    Given a Rust file "src/steps.rs" with content:
+      """
+      use my_wrapper::given;
+      use other::helper as when;
+
+      #[given("from an unlisted wrapper crate")]
+      fn wrapped(world: &mut World) {}
+
+      when!("^not a step either$", || {});
+      """
+    When we extract the Step Index
+    Then there are no steps
+    And there is a "UnknownStepCrate" warning for file "src/steps.rs" at line 5: "`#[given]` is `my_wrapper::given`, from a crate not known to define steps (list `my_wrapper` in `cukerust.detectors.crates` if it does); the step is not indexed"
+    And there is a "UnknownStepCrate" warning for file "src/steps.rs" at line 8: "`when!` is `other::helper`, from a crate not known to define steps (list `other` in `cukerust.detectors.crates` if it does); the step is not indexed"
+    And there are 2 warnings
+
+  Scenario: Qualified paths into unrelated crates are not steps
+    Given a Rust file "src/props.rs" with content:
+      """
+      use cucumber as cuc;
+
+      #[other_crate::given("not a cucumber step")]
+      fn prop() {}
+
+      other::given!("^not a step either$", || {});
+      ::other::then!("^nor this$", || {});
+
+      #[cuc::when("an aliased cucumber path")]
+      fn aliased(world: &mut World) {}
+
+      #[crate::support::then("a path into the crate itself")]
+      fn own(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is a step with kind "When" and regex "an aliased cucumber path" from file "src/props.rs" at line 10 with function "aliased"
+    And there is a step with kind "Then" and regex "a path into the crate itself" from file "src/props.rs" at line 13 with function "own"
+
+  Scenario: Crates declared in the detector config may export step attributes
+    Given the detector config:
+      """
+      { "crates": ["my_steps"] }
+      """
+    And a Rust file "src/steps.rs" with content:
+      """
+      use my_steps::{given, then as and_then};
+
+      #[given("from a wrapper crate")]
+      fn wrapped(world: &mut World) {}
+
+      #[and_then("renamed from a wrapper crate")]
+      fn renamed(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is a step with kind "Then" and regex "renamed from a wrapper crate" from file "src/steps.rs" at line 7 with function "renamed"
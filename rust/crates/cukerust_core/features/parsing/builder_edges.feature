@@ -57,3 +57,35 @@ Feature: Builder chains edge cases
     When we extract the Step Index
     Then the index contains 1 steps
     And there is a step with kind "When" and regex "^eat$" from file "builder_line.rs" at line 5
+
+  Scenario: Builder call wrapped over several lines
+    Given a Rust file "builder_multiline.rs" with content:
+      """
+      fn f() {
+          registry
+              .given(
+                  r"^I have (\d+) cukes$",
+                  handler,
+              )
+              .then::<World>(
+                  "^done$"
+              );
+      }
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is a step with kind "Given" and regex "^I have (\d+) cukes$" from file "builder_multiline.rs" at line 4
+    And there is a step with kind "Then" and regex "^done$" from file "builder_multiline.rs" at line 8
+
+  Scenario: Wrapped builder call without a literal does not borrow a later one
+    Given a Rust file "builder_multiline_variable.rs" with content:
+      """
+      fn f() {
+          registry.given(
+              pattern,
+          );
+          let s = "^not a step$";
+      }
+      """
+    When we extract the Step Index
+    Then there are no steps
@@ -0,0 +1,35 @@
+Feature: Capture/parameter count mismatches are reported as definition warnings
+
+  Scenario: A step function with more parameters than capture groups
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[then(regex = r"^the index contains (\d+) steps$")]
+      fn index_contains(world: &mut CoreWorld, n: usize, extra: String) {}
+      """
+    When we extract the Step Index
+    Then there is a "CaptureCountMismatch" warning for file "src/steps.rs" at line 2: "pattern has 1 capture group but `index_contains` takes 2 step parameters (n, extra)"
+    And there are 1 warnings
+
+  Scenario: Expression parameters without matching function parameters
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(expr = "I have {int} cuke(s) of {word}")]
+      async fn cukes(
+          world: &mut CoreWorld,
+          step: &Step,
+      ) {}
+      """
+    When we extract the Step Index
+    Then there is a "CaptureCountMismatch" warning for file "src/steps.rs" at line 2: "pattern has 2 capture groups but `cukes` takes 0 step parameters"
+
+  Scenario: Matching counts, builders and unresolved patterns are not reported
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[then(regex = r"^(\d+) (?:x|y) (\w+)$")]
+      fn ok(world: &mut CoreWorld, step: &Step, n: usize, s: String) {}
+      #[given(regex = MISSING)]
+      fn missing(world: &mut CoreWorld, n: usize) {}
+      registry.given(r"^I have (\d+) cukes$");
+      """
+    When we extract the Step Index
+    Then there are 0 warnings
@@ -0,0 +1,50 @@
+Feature: Capture metadata from regex groups and function parameters
+
+  Scenario: Attribute groups are paired with the step function parameters
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[then(regex = r"^the index contains (\d+) steps in (?P<file>\S+)$")]
+      async fn index_has(
+          world: &mut CoreWorld,
+          step: &Step,
+          n: usize,
+          file: String,
+      ) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "^the index contains (\d+) steps in (?P<file>\S+)$" has captures:
+      | index | name | type   | start | end |
+      | 1     | n    | usize  | 20    | 25  |
+      | 2     | file | String | 35    | 48  |
+
+  Scenario: Expression parameters are captures
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(expr = "I have {int} cuke(s) of {word}")]
+      fn cukes(w: &mut W, count: i64, kind: String) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "I have {int} cuke(s) of {word}" has captures:
+      | index | name  | type   | start | end |
+      | 1     | count | i64    | 7     | 12  |
+      | 2     | kind  | String | 24    | 30  |
+
+  Scenario: Builder and macro groups keep group names only
+    Given a Rust file "src/steps.rs" with content:
+      """
+      given!(r"^(?P<who>\w+) has (\d+)$", |w, who, n| {});
+      """
+    When we extract the Step Index
+    Then the step with regex "^(?P<who>\w+) has (\d+)$" has captures:
+      | index | name | type | start | end |
+      | 1     | who  |      | 1     | 13  |
+      | 2     |      |      | 18    | 23  |
+
+  Scenario: Patterns without groups have no captures
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given("a (literal) step")]
+      fn literal(w: &mut W) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "a (literal) step" has no captures
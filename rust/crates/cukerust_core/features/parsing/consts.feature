@@ -0,0 +1,59 @@
+Feature: Patterns given as const items and concat!
+
+  Scenario: Attribute regex given as a const from another file of the same crate
+    Given a Rust file "src/common.rs" with content:
+      """
+      pub const AMOUNT: &str = r"(\d+)";
+      pub const GIVEN_BALANCE: &str = r"^my balance is (\d+)$";
+      """
+    And a Rust file "src/steps/account.rs" with content:
+      """
+      use crate::common::GIVEN_BALANCE;
+
+      #[given(regex = GIVEN_BALANCE)]
+      fn balance() {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "Given" and regex "^my balance is (\d+)$" from file "src/steps/account.rs" at line 4 with function "balance"
+    And the step with regex "^my balance is (\d+)$" has pattern kind "Regex"
+
+  Scenario: concat! of literals and consts is folded into one pattern
+    Given a Rust file "src/steps.rs" with content:
+      """
+      const AMOUNT: &str = r"(\d+)";
+      static CUKES: &'static str = " cukes$";
+      given!(concat!("^I have ", AMOUNT, CUKES), || {});
+      fn f() { registry.when(concat!("^I eat ", AMOUNT, "$")); }
+      #[then(expr = concat!("I have {int} ", "left"))]
+      fn left() {}
+      """
+    When we extract the Step Index
+    Then the index contains 3 steps
+    And there is a step with kind "Given" and regex "^I have (\d+) cukes$" from file "src/steps.rs" at line 4
+    And there is a step with kind "When" and regex "^I eat (\d+)$" from file "src/steps.rs" at line 5
+    And the step with regex "I have {int} left" has pattern kind "Expression"
+
+  Scenario: Consts of another crate are not used
+    Given a Rust file "crates/a/src/lib.rs" with content:
+      """
+      pub const SHARED: &str = "^from a$";
+      """
+    And a Rust file "crates/b/src/lib.rs" with content:
+      """
+      given!(SHARED, || {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is an unresolved step "SHARED" from file "crates/b/src/lib.rs" at line 2 because "unknown const `SHARED`"
+
+  Scenario: Unresolvable patterns are flagged instead of dropped
+    Given a Rust file "src/unresolved.rs" with content:
+      """
+      #[given(regex = MISSING)]
+      fn missing() {}
+      given!(concat!("^a", format!("b")), || {});
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is an unresolved step "MISSING" from file "src/unresolved.rs" at line 2 because "unknown const `MISSING`"
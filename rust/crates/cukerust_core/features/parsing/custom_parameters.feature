@@ -0,0 +1,19 @@
+Feature: Custom parameter types deriving cucumber Parameter
+
+  Scenario: Derived parameter types are listed in the Step Index
+    Given a Rust file "src/animal.rs" with content:
+      """
+      #[derive(Debug, Parameter)]
+      #[param(name = "pet", regex = "cat|dog|[a-z]+fish")]
+      pub enum Animal {
+          Cat,
+          Dog,
+      }
+
+      #[derive(cucumber::Parameter)]
+      #[param(regex = r"\d+ (?:kg|g)")]
+      struct Weight(u32);
+      """
+    When we extract the Step Index
+    Then there is a custom parameter "pet" for type "Animal" with regex "cat|dog|[a-z]+fish" at line 2
+    And there is a custom parameter "weight" for type "Weight" with regex "\d+ (?:kg|g)" at line 9
@@ -0,0 +1,54 @@
+Feature: Configurable step detectors
+
+  Scenario: Wrapper macros, attributes and methods are recognised once configured
+    Given the detector config:
+      """
+      {
+        "macros": [{ "name": "step" }],
+        "attributes": [{ "name": "given_async", "kind": "Given" }],
+        "methods": [{ "name": "on_then", "kind": "Then" }]
+      }
+      """
+    And a Rust file "src/steps.rs" with content:
+      """
+      step!(Given, r"^I have (\d+) cukes$", |w, n: usize| {});
+      step!(StepType::When, "^I eat$", |w| {});
+
+      #[given_async("a warm kitchen")]
+      async fn warm(world: &mut World) {}
+
+      fn register(registry: &mut Registry) {
+          registry.on_then("^I am full$", handler);
+      }
+      """
+    When we extract the Step Index
+    Then the index contains 4 steps
+    And there is a step with kind "Given" and regex "^I have (\d+) cukes$" from file "src/steps.rs" at line 2
+    And there is a step with kind "When" and regex "^I eat$" from file "src/steps.rs" at line 3
+    And there is a step with kind "Given" and regex "a warm kitchen" from file "src/steps.rs" at line 5 with function "warm"
+    And there is a step with kind "Then" and regex "^I am full$" from file "src/steps.rs" at line 9
+
+  Scenario: Unconfigured wrappers are not steps
+    Given a Rust file "src/steps.rs" with content:
+      """
+      step!(Given, "^nope$", |w| {});
+      #[given_async("nope")]
+      async fn nope(world: &mut World) {}
+      registry.on_then("^nope$", handler);
+      """
+    When we extract the Step Index
+    Then there are no steps
+
+  Scenario: A configured macro whose first argument names no step kind is skipped
+    Given the detector config:
+      """
+      { "macros": [{ "name": "step" }] }
+      """
+    And a Rust file "src/steps.rs" with content:
+      """
+      step!(Before, "^nope$", |w| {});
+      step!(Then, "^yes$", |w| {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "Then" and regex "^yes$" from file "src/steps.rs" at line 3
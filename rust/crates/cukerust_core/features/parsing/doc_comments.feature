@@ -0,0 +1,32 @@
+Feature: Doc comments as step documentation
+
+  Scenario: Doc comments above attributes, macros and builder calls become notes
+    Given a Rust file "src/steps.rs" with content:
+      """
+      /// Stocks the fridge.
+      ///
+      /// ```gherkin
+      /// Given I have 3 cukes
+      /// ```
+      #[given(regex = r"^I have (\d+) cukes$")]
+      #[doc = "Counts must be positive."]
+      async fn cukes(world: &mut World, n: usize) {}
+
+      #[when("undocumented")]
+      fn plain(world: &mut World) {}
+
+      /// Starts the run.
+      given!("^start$", || {});
+
+      fn register(registry: &mut Registry) {
+          registry
+              // set up
+              /// Eats **all** of them.
+              .when("^eat$", handler);
+      }
+      """
+    When we extract the Step Index
+    Then the step with regex "^I have (\d+) cukes$" has notes "Stocks the fridge.\n\n```gherkin\nGiven I have 3 cukes\n```\nCounts must be positive."
+    And the step with regex "^start$" has notes "Starts the run."
+    And the step with regex "^eat$" has notes "Eats **all** of them."
+    And the step with regex "undocumented" has no notes
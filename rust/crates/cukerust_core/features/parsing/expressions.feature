@@ -0,0 +1,36 @@
+Feature: Cucumber Expression attributes
+
+  Scenario: expr attribute is recorded as a Cucumber Expression
+    Given a Rust file "expr_attr.rs" with content:
+      """
+      #[given(expr = "I have {int} cuke(s)")]
+      fn cukes() {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "Given" and regex "I have {int} cuke(s)" from file "expr_attr.rs" at line 2 with function "cukes"
+    And the step with regex "I have {int} cuke(s)" has pattern kind "Expression"
+
+  Scenario: Multi-line expr attribute with raw string
+    Given a Rust file "expr_multiline.rs" with content:
+      """
+      #[when(
+          expr = r"I eat {float} {word}"
+      )]
+      async fn eat() {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the step with regex "I eat {float} {word}" has pattern kind "Expression"
+
+  Scenario: regex attributes, builders and macros stay regexes
+    Given a Rust file "regex_kinds.rs" with content:
+      """
+      #[then(regex = r"^done$")]
+      fn done() {}
+      given!(r"^start$", || {});
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And the step with regex "^done$" has pattern kind "Regex"
+    And the step with regex "^start$" has pattern kind "Regex"
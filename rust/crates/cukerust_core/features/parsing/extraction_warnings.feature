@@ -0,0 +1,68 @@
+Feature: Step definitions that cannot be extracted are reported as warnings
+
+  Scenario: An attribute whose pattern literal is never closed
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given("an open literal)]
+      fn open(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 0 steps
+    And there is a "UnterminatedLiteral" warning for file "src/steps.rs" at line 2: "`#[given]` has a string literal that is never closed; the step is not indexed"
+    And the warning at line 2 comes from the "Attribute" detector
+    And there are 1 warnings
+
+  Scenario: Macros without a literal, const or concat! pattern
+    Given a Rust file "src/steps.rs" with content:
+      """
+      given!(pattern(), |w| {});
+      then!("^ok$", |w| {});
+      when!(PATTERNS[0], |w| {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a "UnsupportedPattern" warning for file "src/steps.rs" at line 2: "`given!` has no string literal, const or `concat!` pattern; the step is not indexed"
+    And there is a "UnsupportedPattern" warning for file "src/steps.rs" at line 4: "`when!` has no string literal, const or `concat!` pattern; the step is not indexed"
+    And the warning at line 2 comes from the "Macro" detector
+    And there are 2 warnings
+
+  Scenario: Attributes without arguments or a usable pattern
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[then]
+      fn bare(world: &mut World) {}
+
+      #[when(regex = make_pattern())]
+      fn made(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the index contains 0 steps
+    And there is a "MalformedDefinition" warning for file "src/steps.rs" at line 2: "`#[then]` has no arguments; the step is not indexed"
+    And there is a "UnsupportedPattern" warning for file "src/steps.rs" at line 5: "`#[when]` has no string literal, const or `concat!` pattern; the step is not indexed"
+    And there are 2 warnings
+
+  Scenario: A kind argument that is not a step kind
+    Given the detector config:
+      """
+      { "macros": [{ "name": "step" }] }
+      """
+    And a Rust file "src/steps.rs" with content:
+      """
+      step!(Given, "^fine$", |w| {});
+      step!(Later, "^not a kind$", |w| {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a "MalformedDefinition" warning for file "src/steps.rs" at line 3: "the first argument of `step!` is not a step kind (Given, When or Then); the step is not indexed"
+    And there are 1 warnings
+
+  Scenario: Builder calls on other types are not reported
+    Given a Rust file "src/steps.rs" with content:
+      """
+      let ready = flag.then(|| 1);
+      let next = future.then(|x| async move { x });
+      registry.given(r"^I have (\d+) cukes$");
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there are 0 warnings
@@ -23,3 +23,76 @@ Feature: False positives and robustness
       """
     When we extract the Step Index
     Then there are no steps
+
+  Scenario: Nested block comments hide everything up to their outermost close
+    Given a Rust file "nested_comment.rs" with content:
+      """
+      /* outer /* inner */ given!(r"^hidden$", || {}); */
+      when!(r"^visible$", || {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "When" and regex "^visible$" from file "nested_comment.rs" at line 3
+
+  Scenario: A double-quote char literal does not start a string
+    Given a Rust file "char_quote.rs" with content:
+      """
+      given!(r"^quote$", |w| { w.c = '"'; });
+      fn f() { let q = '"'; } // then!(r"^commented$", || {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the definition span of the step with regex "^quote$" covers "given!(r"^quote$", |w| { w.c = '"'; })" from 2:0 to 2:38
+
+  Scenario: Byte-string and raw byte-string literals are skipped whole
+    Given a Rust file "byte_strings.rs" with content:
+      """
+      given!(r"^bytes$", |w| { w.path = br"C:\"; w.raw = b"\"(given!)"; });
+      fn f() { let b = b"given!(\"^inside$\")"; } // when!(r"^commented$", || {});
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the definition span of the step with regex "^bytes$" covers "given!(r"^bytes$", |w| { w.path = br"C:\"; w.raw = b"\"(given!)"; })" from 2:0 to 2:68
+
+  Scenario: C-string literals are skipped whole
+    Given a Rust file "c_strings.rs" with content:
+      """
+      when!(r"^c strings$", |w| { w.dir = cr"C:\"; w.name = c"x"; });
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the definition span of the step with regex "^c strings$" covers "when!(r"^c strings$", |w| { w.dir = cr"C:\"; w.name = c"x"; })" from 2:0 to 2:62
+
+  Scenario: Lifetimes are not char literals
+    Given a Rust file "lifetimes.rs" with content:
+      """
+      fn f<'a>(s: &'a str) -> &'a str { given!(r"^after lifetimes$", |w| { w.c = 'x'; }); s }
+      """
+    When we extract the Step Index
+    Then there is a step with kind "Given" and regex "^after lifetimes$" from file "lifetimes.rs" at line 2
+
+  Scenario: Raw identifiers are not raw strings
+    Given a Rust file "raw_idents.rs" with content:
+      """
+      fn r#given(r#then: &str) {}
+      fn register(registry: &mut Registry) { let r#when = 1; registry.when("^after raw idents$", r#given); }
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "When" and regex "^after raw idents$" from file "raw_idents.rs" at line 3
+
+  Scenario: String literals inside a closure argument are not patterns
+    Given a Rust file "closures.rs" with content:
+      """
+      async fn run(fut: impl Future<Output = u8>) -> u8 {
+          fut.then(|res| async move {
+              tracing::info!("finished run");
+              res
+          })
+          .await;
+          when!(|w| { log("^nope$"); }, r"^nor this$");
+          registry.given(format!("^{}$", name));
+      }
+      """
+    When we extract the Step Index
+    Then there are no steps
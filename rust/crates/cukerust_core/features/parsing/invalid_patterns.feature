@@ -0,0 +1,51 @@
+Feature: Step patterns that do not compile are flagged at index time
+
+  Scenario: A regex with an unclosed group
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(regex = r"^I have (\d+ cukes$")]
+      fn broken(world: &mut World) {}
+
+      #[then(regex = r"^I have (\d+) cukes$")]
+      fn fine(world: &mut World, n: usize) {}
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And the step with regex "^I have (\d+ cukes$" is invalid because "invalid regex: unclosed group at offset 8"
+    And the step with regex "^I have (\d+) cukes$" is valid
+    And there is a "InvalidPattern" warning for file "src/steps.rs" at line 2: "invalid regex: unclosed group at offset 8; the step never matches"
+    And there are 1 warnings
+
+  Scenario: A regex with an unknown escape in a macro
+    Given a Rust file "src/steps.rs" with content:
+      """
+      when!(r"^I press \q$", |w| {});
+      """
+    When we extract the Step Index
+    Then the step with regex "^I press \q$" is invalid because "invalid regex: unrecognized escape sequence at offset 9"
+    And there are 1 warnings
+
+  Scenario: A Cucumber Expression with unterminated optional text
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(expr = "I have {int} cuke(s")]
+      fn cukes(world: &mut World, n: i32) {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the step with regex "I have {int} cuke(s" is invalid because "invalid Cucumber Expression: unterminated optional text at offset 17"
+    And there is a "InvalidPattern" warning for file "src/steps.rs" at line 2: "invalid Cucumber Expression: unterminated optional text at offset 17; the step never matches"
+    And there are 1 warnings
+
+  Scenario: Literal and unresolved patterns are not validated
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given("a (literal with [brackets")]
+      fn literal(world: &mut World) {}
+
+      #[when(regex = PATTERN)]
+      fn unresolved(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "a (literal with [brackets" is valid
+    And there are 0 warnings
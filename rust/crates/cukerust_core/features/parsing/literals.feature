@@ -0,0 +1,38 @@
+Feature: Literal step attributes
+
+  Scenario: Attribute with a bare literal is an exact-text step
+    Given a Rust file "literal_attr.rs" with content:
+      """
+      #[given("a Rust file with attribute given")]
+      fn literal() {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And there is a step with kind "Given" and regex "a Rust file with attribute given" from file "literal_attr.rs" at line 2 with function "literal"
+    And the step with regex "a Rust file with attribute given" has pattern kind "Literal"
+
+  Scenario: Literal text with regex metacharacters is kept verbatim
+    Given a Rust file "literal_meta.rs" with content:
+      """
+      #[then("a file (v1.0) exists?")]
+      fn meta() {}
+      """
+    When we extract the Step Index
+    Then the index contains 1 steps
+    And the step with regex "a file (v1.0) exists?" has pattern kind "Literal"
+
+  Scenario: Attribute argument form selects the pattern kind
+    Given a Rust file "attr_forms.rs" with content:
+      """
+      #[given("exact text")]
+      fn a() {}
+      #[given(regex = r"^a regex$")]
+      fn b() {}
+      #[given(expr = "an {int}")]
+      fn c() {}
+      """
+    When we extract the Step Index
+    Then the index contains 3 steps
+    And the step with regex "exact text" has pattern kind "Literal"
+    And the step with regex "^a regex$" has pattern kind "Regex"
+    And the step with regex "an {int}" has pattern kind "Expression"
@@ -12,11 +12,12 @@ Feature: Macros edge cases
   Scenario: Module-path macro
     Given a Rust file "macro_module.rs" with content:
       """
+      use crate::my;
       my::given!(r"^x$", || {});
       """
     When we extract the Step Index
     Then the index contains 1 steps
-    And there is a step with kind "Given" and regex "^x$" from file "macro_module.rs" at line 2
+    And there is a step with kind "Given" and regex "^x$" from file "macro_module.rs" at line 3
 
   Scenario: Macro lookalike names not discovered
     Given a Rust file "macro_lookalike.rs" with content:
@@ -43,3 +44,20 @@ Feature: Macros edge cases
     Then the index contains 2 steps
     And there is a step with kind "Given" and regex "^a$" from file "macro_same_line.rs" at line 2
     And there is a step with kind "When" and regex "^b$" from file "macro_same_line.rs" at line 2
+
+  Scenario: Macro invocation wrapped over several lines
+    Given a Rust file "macro_multiline.rs" with content:
+      """
+      given!(
+          r"^start$",
+          |world| {},
+      );
+      cucumber::then!
+      (
+          "^end$", handler
+      );
+      """
+    When we extract the Step Index
+    Then the index contains 2 steps
+    And there is a step with kind "Given" and regex "^start$" from file "macro_multiline.rs" at line 2
+    And there is a step with kind "Then" and regex "^end$" from file "macro_multiline.rs" at line 6
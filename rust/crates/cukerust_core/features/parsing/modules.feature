@@ -0,0 +1,36 @@
+Feature: Module paths of step definitions
+
+  Scenario: The module path comes from the file path
+    Given a Rust file "src/steps/account.rs" with content:
+      """
+      #[given(regex = r"^a user$")]
+      fn given_user(world: &mut World) {}
+      """
+    And a Rust file "crates/shop/tests/steps/mod.rs" with content:
+      """
+      #[given(regex = r"^a shop user$")]
+      fn given_user(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "^a user$" is in module "crate::steps::account" with function "given_user"
+    And the step with regex "^a shop user$" is in module "crate::steps" with function "given_user"
+
+  Scenario: Inline modules extend the file's module path
+    Given a Rust file "src/lib.rs" with content:
+      """
+      mod steps {
+          pub mod admin {
+              #[when(regex = r"^the admin logs in$")]
+              fn logs_in(world: &mut World) {}
+          }
+          fn register(registry: &mut Registry) {
+              registry.then(r"^registered$");
+          }
+      }
+      #[then(regex = r"^top level$")]
+      fn top(world: &mut World) {}
+      """
+    When we extract the Step Index
+    Then the step with regex "^the admin logs in$" is in module "crate::steps::admin" with function "logs_in"
+    And the step with regex "^registered$" is in module "crate::steps"
+    And the step with regex "^top level$" is in module "crate" with function "top"
@@ -0,0 +1,82 @@
+Feature: Same-kind steps with different patterns that match a common step text
+
+  Scenario: Two regexes accepting the same step text
+    Given a Rust file "src/steps/cukes.rs" with content:
+      """
+      #[given(regex = r"^I have (\d+) cukes$")]
+      fn digits(world: &mut World, n: usize) {}
+
+      #[given(regex = r"^I have (.*) cukes$")]
+      fn anything(world: &mut World, n: String) {}
+
+      #[when(regex = r"^I have (.*) cukes$")]
+      fn other_kind(world: &mut World, n: String) {}
+      """
+    When we extract the Step Index with overlaps
+    Then the index ambiguous count is 0
+    And there is a Given overlap with witness "I have 0 cukes" between:
+      | file               | line |
+      | src/steps/cukes.rs | 2    |
+      | src/steps/cukes.rs | 5    |
+    And there are 1 overlaps
+
+  Scenario: Literals, Cucumber Expressions and unanchored regexes across files
+    Given a Rust file "src/a.rs" with content:
+      """
+      #[then("the basket holds 3 apples")]
+      fn three(world: &mut World) {}
+      """
+    And a Rust file "src/b.rs" with content:
+      """
+      #[then(expr = "the basket holds {int} apple(s)")]
+      fn count(world: &mut World, n: i32) {}
+
+      then!(r"holds \d+ pears", |w| {});
+      """
+    When we extract the Step Index with overlaps
+    Then there is a Then overlap with witness "the basket holds 3 apples" between:
+      | file     | line |
+      | src/a.rs | 2    |
+      | src/b.rs | 2    |
+    And there are 1 overlaps
+
+  Scenario: Disjoint patterns and identical duplicates are not overlaps
+    Given a Rust file "src/steps.rs" with content:
+      """
+      given!(r"^I have (\d+) cukes$", |w, n: usize| {});
+      given!(r"^I have ([a-z]+) cukes$", |w, n: String| {});
+      given!(r"^I have (\d+) cukes$", |w, n: usize| {});
+      given!(r"^I eat", |w| {});
+      given!(r"apples$", |w| {});
+      """
+    When we extract the Step Index with overlaps
+    Then the index ambiguous count is 1
+    And there are 0 overlaps
+
+  Scenario: Unanchored regexes are compared as whole-text patterns
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(regex = r"I have (\d+) cukes")]
+      fn digits(world: &mut World, n: usize) {}
+
+      #[given(regex = r"I have (.*) cukes")]
+      fn anything(world: &mut World, n: String) {}
+
+      #[given(regex = r"cukes")]
+      fn mentions(world: &mut World) {}
+      """
+    When we extract the Step Index with overlaps
+    Then there is a Given overlap with witness "I have 0 cukes" between:
+      | file         | line |
+      | src/steps.rs | 2    |
+      | src/steps.rs | 5    |
+    And there are 1 overlaps
+
+  Scenario: Overlaps are only computed on request
+    Given a Rust file "src/steps.rs" with content:
+      """
+      given!(r"^I have (\d+) cukes$", |w, n: usize| {});
+      given!(r"^I have (.*) cukes$", |w, n: String| {});
+      """
+    When we extract the Step Index
+    Then there are 0 overlaps
@@ -0,0 +1,29 @@
+Feature: Source spans of step definitions
+
+  Scenario: Attribute steps record the definition, pattern and function name
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(regex = r"^I have (\d+) cukes$")]
+      async fn cukes(world: &mut World, n: usize) {
+          world.n = n;
+      }
+      """
+    When we extract the Step Index
+    Then the pattern span of the step with regex "^I have (\d+) cukes$" covers "r"^I have (\d+) cukes$"" from 2:16 to 2:39
+    And the function span of the step with regex "^I have (\d+) cukes$" covers "cukes" from 3:9 to 3:14
+    And the definition span of the step with regex "^I have (\d+) cukes$" covers "#[given(regex = r"^I have (\d+) cukes$")]\nasync fn cukes(world: &mut World, n: usize) {\n    world.n = n;\n}" from 2:0 to 5:1
+
+  Scenario: Builder and macro steps span the call
+    Given a Rust file "src/steps.rs" with content:
+      """
+      fn register(registry: &mut Registry) {
+          registry.given(NAME, handler).when("^eat (.*)$", |w| {});
+      }
+      const NAME: &str = "^named$";
+      given!("^start$", || {});
+      """
+    When we extract the Step Index
+    Then the definition span of the step with regex "^named$" covers "given(NAME, handler)" from 3:13 to 3:33
+    And the pattern span of the step with regex "^named$" covers "NAME" from 3:19 to 3:23
+    And the definition span of the step with regex "^eat (.*)$" covers "when("^eat (.*)$", |w| {})" from 3:34 to 3:60
+    And the definition span of the step with regex "^start$" covers "given!("^start$", || {})" from 6:0 to 6:24
@@ -21,3 +21,36 @@ Feature: Statistics and ambiguity semantics
     Then the index contains 4 steps
     And the index has Given, When, Then kinds counted
     And the index ambiguous count is 1
+
+  Scenario: Ambiguity groups list every definition, with per-file and per-detector counts
+    Given a Rust file "crates/payments/tests/steps/pay.rs" with content:
+      """
+      #[given(regex = r"^a balance$")]
+      fn balance(world: &mut World) {}
+
+      given!(r"^a balance$", |w| {});
+
+      #[when("I pay")]
+      fn pay(world: &mut World) {}
+      """
+    And a Rust file "crates/payments/tests/steps/refund.rs" with content:
+      """
+      fn register(r: &mut Registry) {
+          r.given(r"^a balance$", |w| {});
+          r.when("I pay", |w| {});
+      }
+      """
+    When we extract the Step Index
+    Then the index ambiguous count is 2
+    And there is a Given ambiguity for "^a balance$" at:
+      | file                                  | line | function |
+      | crates/payments/tests/steps/pay.rs    | 2    | balance  |
+      | crates/payments/tests/steps/pay.rs    | 5    |          |
+      | crates/payments/tests/steps/refund.rs | 3    |          |
+    And there is a When ambiguity for "I pay" at:
+      | file                                  | line | function |
+      | crates/payments/tests/steps/pay.rs    | 7    | pay      |
+      | crates/payments/tests/steps/refund.rs | 4    |          |
+    And file "crates/payments/tests/steps/pay.rs" has 3 steps, 3 of them ambiguous
+    And file "crates/payments/tests/steps/refund.rs" has 2 steps, 2 of them ambiguous
+    And the steps by detector are 2 Attribute, 1 Macro, 2 Builder and 0 Other
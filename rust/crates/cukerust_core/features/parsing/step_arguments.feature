@@ -0,0 +1,33 @@
+Feature: DataTable and DocString usage of step definitions
+
+  Scenario: Attribute steps record whether they read the table or docstring
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given("the following users")]
+      fn users(world: &mut World, step: &Step) {
+          let table = step.table.as_ref().unwrap();
+      }
+      #[when("I post the body")]
+      fn post(world: &mut World, step: &Step) {
+          world.body = step.docstring.clone();
+      }
+      #[then("nothing else")]
+      fn plain(world: &mut World) {}
+      #[then("it is delegated")]
+      fn delegated(world: &mut World, step: &Step) {
+          helper(world, step);
+      }
+      """
+    When we extract the Step Index
+    Then the step with regex "the following users" expects a table "yes" and a docstring "no"
+    And the step with regex "I post the body" expects a table "no" and a docstring "yes"
+    And the step with regex "nothing else" expects a table "no" and a docstring "no"
+    And the step with regex "it is delegated" expects a table "unknown" and a docstring "unknown"
+
+  Scenario: Builder and macro steps leave usage unknown
+    Given a Rust file "src/steps.rs" with content:
+      """
+      given!("^start$", |w, step| {});
+      """
+    When we extract the Step Index
+    Then the step with regex "^start$" expects a table "unknown" and a docstring "unknown"
@@ -0,0 +1,462 @@
+//! Semantic ambiguity: same-kind steps whose patterns differ but match a common step text.
+//! cucumber-rs searches regex patterns anywhere in the step text and matches literals and
+//! Cucumber Expressions against all of it; a text matched by two definitions fails at
+//! runtime with an ambiguous match error. Each pattern is compiled into a lazy DFA deciding
+//! whether a whole step text matches, and the product of two such automata is searched
+//! breadth first for the shortest text both accept.
+//!
+//! A regex anchored at neither end is compared as if anchored at both, i.e. as the step
+//! text it was written for: searched, it would overlap every step whose text contains one
+//! of its matches. A regex anchored only at its start and one anchored only at its end
+//! always overlap (a text can hold one match followed by the other); such pairs are not
+//! reported.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use regex_automata::hybrid::dfa::{Cache, DFA};
+use regex_automata::hybrid::LazyStateID;
+use regex_automata::{Anchored, Input, MatchKind};
+use regex_syntax::hir::Look;
+
+use crate::cucumber_expression;
+use crate::step_index::{Overlap, PatternKind, StepEntry, StepKind, StepLocation};
+
+/// Product states explored for a pair of patterns before giving up on it.
+const MAX_PAIR_STATES: usize = 10_000;
+
+/// Returns a step text matched by both `a` and `b` (the shortest one, preferring lowercase
+/// letters, digits and spaces), or `None` when their patterns are disjoint, unresolved,
+/// invalid or too complex to intersect. The kinds of the steps are not compared.
+pub fn overlap_witness(a: &StepEntry, b: &StepEntry) -> Option<String> {
+    witness(&mut Language::of(a, false)?, &mut Language::of(b, false)?)
+}
+
+/// Overlaps between the distinct patterns of each kind. Steps sharing a kind and the
+/// same pattern text are left to [`crate::step_index::Stats::ambiguous`].
+pub(crate) fn overlaps<'a>(steps: impl IntoIterator<Item = &'a StepEntry>) -> Vec<Overlap> {
+    let mut patterns: Vec<Vec<&StepEntry>> = Vec::new();
+    let mut seen: HashMap<(StepKind, &str), usize> = HashMap::new();
+    for step in steps {
+        match seen.get(&(step.kind, step.regex.as_str())) {
+            Some(&i) => patterns[i].push(step),
+            None => {
+                seen.insert((step.kind, step.regex.as_str()), patterns.len());
+                patterns.push(vec![step]);
+            }
+        }
+    }
+    let affixes: Vec<Affixes> = patterns.iter().map(|defs| Affixes::of(defs[0])).collect();
+    let mut candidates = Vec::new();
+    for kind in [StepKind::Given, StepKind::When, StepKind::Then] {
+        let same: Vec<usize> = (0..patterns.len()).filter(|&i| patterns[i][0].kind == kind).collect();
+        candidates.extend(candidate_pairs(&same, &affixes));
+    }
+    candidates.sort_unstable();
+    let mut languages: Vec<Option<Option<Language>>> = (0..patterns.len()).map(|_| None).collect();
+    let mut out = Vec::new();
+    for (i, j) in candidates {
+        for k in [i, j] {
+            if languages[k].is_none() {
+                languages[k] = Some(Language::of(patterns[k][0], affixes[k].whole));
+            }
+        }
+        let (before, rest) = languages.split_at_mut(j);
+        let (Some(Some(left)), Some(Some(right))) = (&mut before[i], &mut rest[0]) else {
+            continue;
+        };
+        let Some(witness) = witness(left, right) else {
+            continue;
+        };
+        let mut steps: Vec<StepLocation> = patterns[i].iter().chain(&patterns[j]).map(|s| StepLocation::of(s)).collect();
+        steps.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        out.push(Overlap { kind: patterns[i][0].kind, witness, steps });
+    }
+    out.sort_by(|a, b| a.steps.iter().map(StepLocation::sort_key).cmp(b.steps.iter().map(StepLocation::sort_key)).then(a.kind.cmp(&b.kind)));
+    out
+}
+
+/// Pairs `(i, j)`, `i < j`, of the patterns `group` whose affixes agree. Rather than
+/// testing every pair, each pattern is tested against the patterns whose prefix extends or
+/// is extended by its own, or those related the same way by suffix, whichever are fewer;
+/// only patterns with neither a prefix nor a suffix are tested against all others.
+fn candidate_pairs(group: &[usize], affixes: &[Affixes]) -> Vec<(usize, usize)> {
+    let by_prefix = AffixIndex::new(group, affixes.len(), |i| affixes[i].prefix.clone());
+    let by_suffix = AffixIndex::new(group, affixes.len(), |i| affixes[i].suffix.chars().rev().collect());
+    let mut out = Vec::new();
+    for &i in group {
+        let fewer = if by_prefix.count(i) <= by_suffix.count(i) { &by_prefix } else { &by_suffix };
+        for j in fewer.related(i).filter(|&j| j > i) {
+            let (a, b) = (&affixes[i], &affixes[j]);
+            let chained = (a.open_end && b.open_start) || (b.open_end && a.open_start);
+            if !chained && a.prefixes_agree(b) && a.suffixes_agree(b) {
+                out.push((i, j));
+            }
+        }
+    }
+    out
+}
+
+/// Patterns by one affix: those whose affix starts with a given one are a range of
+/// `sorted`, those whose affix is a shorter start of it are found by exact lookups.
+struct AffixIndex {
+    /// The affix of each pattern of the group, by pattern index.
+    keys: Vec<String>,
+    sorted: Vec<usize>,
+    exact: HashMap<String, Vec<usize>>,
+    /// Distinct byte lengths of the affixes, ascending: only starts of these lengths can
+    /// be found in `exact`.
+    lens: Vec<usize>,
+}
+
+impl AffixIndex {
+    fn new(group: &[usize], patterns: usize, key: impl Fn(usize) -> String) -> AffixIndex {
+        let mut keys = vec![String::new(); patterns];
+        for &i in group {
+            keys[i] = key(i);
+        }
+        let mut sorted = group.to_vec();
+        sorted.sort_by(|&a, &b| keys[a].cmp(&keys[b]));
+        let mut exact: HashMap<String, Vec<usize>> = HashMap::new();
+        for &i in group {
+            exact.entry(keys[i].clone()).or_default().push(i);
+        }
+        let mut lens: Vec<usize> = exact.keys().map(String::len).collect();
+        lens.sort_unstable();
+        lens.dedup();
+        AffixIndex { keys, sorted, exact, lens }
+    }
+
+    /// The patterns whose affix starts with that of `i` (including `i`) or is a shorter
+    /// start of it.
+    fn related(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let key = &self.keys[i];
+        self.sorted[self.extending(key)].iter().chain(self.shorter(key).flatten()).copied()
+    }
+
+    /// How many patterns [`AffixIndex::related`] yields.
+    fn count(&self, i: usize) -> usize {
+        let key = &self.keys[i];
+        self.extending(key).len() + self.shorter(key).map(Vec::len).sum::<usize>()
+    }
+
+    /// The patterns of each affix that is a shorter start of `key`.
+    fn shorter<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Vec<usize>> + 'a {
+        let lens = self.lens.iter().take_while(move |&&len| len < key.len());
+        lens.filter(|&&len| key.is_char_boundary(len)).filter_map(|&len| self.exact.get(&key[..len]))
+    }
+
+    /// The range of `sorted` whose affixes start with `key`.
+    fn extending(&self, key: &str) -> std::ops::Range<usize> {
+        let from = self.sorted.partition_point(|&j| self.keys[j].as_str() < key);
+        from..from + self.sorted[from..].partition_point(|&j| self.keys[j].starts_with(key))
+    }
+}
+
+/// Text that every step text a pattern matches starts and ends with (empty when unknown),
+/// whether its matches may start or end anywhere in the text, and whether it is compared
+/// as a whole-text pattern although cucumber-rs searches for it.
+struct Affixes {
+    prefix: String,
+    suffix: String,
+    open_start: bool,
+    open_end: bool,
+    whole: bool,
+}
+
+impl Affixes {
+    fn of(step: &StepEntry) -> Affixes {
+        let closed = |prefix, suffix| Affixes { prefix, suffix, open_start: false, open_end: false, whole: false };
+        let regex = step.regex.as_str();
+        let (start, end) = match step.pattern_kind {
+            PatternKind::Literal => return closed(regex.to_string(), regex.to_string()),
+            PatternKind::Expression => return closed(expression_prefix(regex), expression_suffix(regex)),
+            // alternations and flags may reach past the anchors
+            PatternKind::Regex if regex.contains('|') || regex.contains("(?") => {
+                let (start, end) = regex_syntax::parse(regex).map_or((false, false), |hir| {
+                    let props = hir.properties();
+                    (props.look_set_prefix().contains(Look::Start), props.look_set_suffix().contains(Look::End))
+                });
+                let affixes = Affixes { open_start: !start, open_end: !end, ..closed(String::new(), String::new()) };
+                return if start || end { affixes } else { Affixes { whole: true, ..closed(String::new(), String::new()) } };
+            }
+            PatternKind::Regex => (regex.strip_prefix('^'), regex.strip_suffix('$').filter(|r| !r.ends_with('\\'))),
+        };
+        match (start, end) {
+            (None, None) => Affixes { whole: true, ..closed(literal_prefix(regex), literal_suffix(regex)) },
+            (start, end) => Affixes {
+                prefix: start.map(literal_prefix).unwrap_or_default(),
+                suffix: end.map(literal_suffix).unwrap_or_default(),
+                open_start: start.is_none(),
+                open_end: end.is_none(),
+                whole: false,
+            },
+        }
+    }
+
+    fn prefixes_agree(&self, other: &Affixes) -> bool {
+        self.prefix.starts_with(&other.prefix) || other.prefix.starts_with(&self.prefix)
+    }
+
+    fn suffixes_agree(&self, other: &Affixes) -> bool {
+        self.suffix.ends_with(&other.suffix) || other.suffix.ends_with(&self.suffix)
+    }
+}
+
+const REGEX_META: &str = "\\.[](){}*+?|^$";
+
+/// Literal text at the start of a regex (its `^` removed), up to the first
+/// metacharacter; a character made optional by the quantifier after it is left out.
+fn literal_prefix(regex: &str) -> String {
+    let mut out = String::new();
+    let mut chars = regex.chars().peekable();
+    while let Some(mut c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(n) if !n.is_alphanumeric() => c = n,
+                _ => break,
+            }
+        } else if REGEX_META.contains(c) {
+            break;
+        }
+        if matches!(chars.peek(), Some('?' | '*' | '{')) {
+            break;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Literal text at the end of a regex (its `$` removed), back to the first
+/// metacharacter, escape or quantified character.
+fn literal_suffix(regex: &str) -> String {
+    let chars: Vec<char> = regex.chars().collect();
+    let literal = chars.iter().enumerate().rev().take_while(|&(at, &c)| !REGEX_META.contains(c) && (at == 0 || chars[at - 1] != '\\')).count();
+    chars[chars.len() - literal..].iter().collect()
+}
+
+/// Text before the first parameter, optional text or escape. An alternation drops the
+/// word it is in.
+fn expression_prefix(expr: &str) -> String {
+    let end = expr.find(['{', '(', '\\', '/']).unwrap_or(expr.len());
+    let mut out = &expr[..end];
+    if expr[end..].starts_with('/') {
+        out = out.rfind(char::is_whitespace).map_or("", |ws| &out[..=ws]);
+    }
+    out.to_string()
+}
+
+/// Text after the last parameter, optional text or escape. An alternation drops the word
+/// it is in.
+fn expression_suffix(expr: &str) -> String {
+    let Some(at) = expr.rfind(['}', ')', '\\', '/']) else {
+        return expr.to_string();
+    };
+    let mut out = &expr[at + 1..];
+    if expr[at..].starts_with('/') {
+        out = out.find(char::is_whitespace).map_or("", |ws| &out[ws..]);
+    }
+    out.to_string()
+}
+
+/// The step texts a pattern matches, as cucumber-rs matches it.
+struct Language {
+    dfa: DFA,
+    cache: Cache,
+}
+
+impl Language {
+    /// With `whole`, a regex must match the whole text, like literals and expressions.
+    fn of(step: &StepEntry, whole: bool) -> Option<Language> {
+        if step.unresolved.is_some() || step.invalid.is_some() {
+            return None;
+        }
+        let pattern = match step.pattern_kind {
+            // `Regex::captures` searches, so the match may start and end anywhere
+            PatternKind::Regex if whole => format!("(?:{})", step.regex),
+            PatternKind::Regex => format!("(?s:.)*(?:{})(?s:.)*", step.regex),
+            PatternKind::Literal => format!("^{}$", regex::escape(&step.regex)),
+            PatternKind::Expression => cucumber_expression::to_regex(&step.regex).ok()?,
+        };
+        // `\b` gives up on non-ASCII text instead of failing the build
+        let config = DFA::config().match_kind(MatchKind::All).unicode_word_boundary(true);
+        let dfa = DFA::builder().configure(config).build(&pattern).ok()?;
+        let cache = dfa.create_cache();
+        Some(Language { dfa, cache })
+    }
+
+    fn start(&mut self) -> Option<LazyStateID> {
+        self.dfa.start_state_forward(&mut self.cache, &Input::new("").anchored(Anchored::Yes)).ok()
+    }
+
+    /// Whether a text that led to `state` is in the language.
+    fn accepts(&mut self, state: LazyStateID) -> bool {
+        self.dfa.next_eoi_state(&mut self.cache, state).is_ok_and(|eoi| eoi.is_match())
+    }
+
+    /// The state after `byte`, `None` once no text can be accepted any more.
+    fn next(&mut self, state: LazyStateID, byte: u8) -> Option<LazyStateID> {
+        let next = self.dfa.next_state(&mut self.cache, state, byte).ok()?;
+        (!next.is_dead() && !next.is_quit()).then_some(next)
+    }
+}
+
+/// All bytes, in the order witnesses prefer them.
+fn byte_order() -> impl Iterator<Item = u8> {
+    let other_printable = (0x21..0x7f).filter(|b: &u8| !b.is_ascii_alphanumeric());
+    (b'a'..=b'z').chain(b'0'..=b'9').chain([b' ']).chain(b'A'..=b'Z').chain(other_printable).chain(0x80..=0xff).chain(0..0x20).chain([0x7f])
+}
+
+/// Breadth-first search of the product automaton for the shortest text both accept.
+fn witness(a: &mut Language, b: &mut Language) -> Option<String> {
+    let clears = (a.cache.clear_count(), b.cache.clear_count());
+    let start = (a.start()?, b.start()?);
+    // one byte per pair of equivalence classes is enough to explore every transition
+    let mut classes = HashSet::new();
+    let bytes: Vec<u8> = byte_order().filter(|&x| classes.insert((a.dfa.byte_classes().get(x), b.dfa.byte_classes().get(x)))).collect();
+    type Pair = (LazyStateID, LazyStateID);
+    let mut parents: HashMap<Pair, Option<(Pair, u8)>> = HashMap::from([(start, None)]);
+    let mut queue = VecDeque::from([start]);
+    let mut found = None;
+    while let Some(pair) = queue.pop_front() {
+        if a.accepts(pair.0) && b.accepts(pair.1) {
+            found = Some(pair);
+            break;
+        }
+        if parents.len() > MAX_PAIR_STATES {
+            return None;
+        }
+        for &byte in &bytes {
+            let (Some(next_a), Some(next_b)) = (a.next(pair.0, byte), b.next(pair.1, byte)) else {
+                continue;
+            };
+            if let std::collections::hash_map::Entry::Vacant(slot) = parents.entry((next_a, next_b)) {
+                slot.insert(Some((pair, byte)));
+                queue.push_back((next_a, next_b));
+            }
+        }
+    }
+    // state ids are only stable while neither cache is cleared
+    if (a.cache.clear_count(), b.cache.clear_count()) != clears {
+        return None;
+    }
+    let mut text = Vec::new();
+    let mut at = found?;
+    while let Some((parent, byte)) = parents[&at] {
+        text.push(byte);
+        at = parent;
+    }
+    text.reverse();
+    String::from_utf8(text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step_index;
+
+    fn entry(kind: StepKind, pattern_kind: PatternKind, regex: &str, line: usize) -> StepEntry {
+        StepEntry { pattern_kind, line, ..step_index::entry(kind, regex) }
+    }
+
+    fn regex(regex: &str) -> StepEntry {
+        entry(StepKind::Given, PatternKind::Regex, regex, 1)
+    }
+
+    #[test]
+    fn test_overlap_witness() {
+        assert_eq!(overlap_witness(&regex(r"^I have (\d+) cukes$"), &regex(r"^I have (.*) cukes$")).as_deref(), Some("I have 0 cukes"));
+        assert_eq!(overlap_witness(&regex(r"^I have (\d+) cukes$"), &regex(r"^I have (\w+) cukes$")).as_deref(), Some("I have 0 cukes"));
+        assert_eq!(overlap_witness(&regex(r"^I have (\d+) cukes$"), &regex(r"^I have (\d+) apples$")), None);
+        assert_eq!(overlap_witness(&regex(r"^I have (\d+) cukes$"), &regex(r"^I have ([a-z]+) cukes$")), None);
+        // unanchored patterns match anywhere in the text
+        assert_eq!(overlap_witness(&regex(r"^I eat"), &regex(r"apples$")).as_deref(), Some("I eatapples"));
+        assert_eq!(overlap_witness(&regex(r"cukes"), &regex(r"^I have \d+ cukes$")).as_deref(), Some("I have 0 cukes"));
+        // literals and expressions match the whole text
+        let literal = entry(StepKind::Given, PatternKind::Literal, "I have 5 cukes", 1);
+        let expression = entry(StepKind::Given, PatternKind::Expression, "I have {int} cuke(s)", 1);
+        assert_eq!(overlap_witness(&literal, &expression).as_deref(), Some("I have 5 cukes"));
+        assert_eq!(overlap_witness(&literal, &regex(r"^I have \d+ cukes?$")).as_deref(), Some("I have 5 cukes"));
+        assert_eq!(overlap_witness(&literal, &regex(r"^I have \d+$")), None);
+        let unresolved = StepEntry { unresolved: Some("not a literal".into()), ..regex(".*") };
+        assert_eq!(overlap_witness(&unresolved, &regex(".*")), None);
+    }
+
+    #[test]
+    fn test_affixes() {
+        assert_eq!(literal_prefix(r"I have (\d+) cukes$"), "I have ");
+        assert_eq!(literal_prefix(r"a\.b apples?"), "a.b apple");
+        assert_eq!(literal_prefix(r"\d+"), "");
+        assert_eq!(literal_suffix(r"^I have (\d+) cukes"), " cukes");
+        assert_eq!(literal_suffix(r"^I have \d"), "");
+        let of = |regex| {
+            let a = Affixes::of(&self::regex(regex));
+            (a.prefix, a.suffix, a.open_start, a.open_end, a.whole)
+        };
+        assert_eq!(of(r"^I have (\d+) cukes$"), ("I have ".into(), " cukes".into(), false, false, false));
+        assert_eq!(of(r"^I have cukes\$"), ("I have cukes$".into(), "".into(), false, true, false));
+        assert_eq!(of(r"cukes$"), ("".into(), "cukes".into(), true, false, false));
+        assert_eq!(of(r"I have (\d+) cukes"), ("I have ".into(), " cukes".into(), false, false, true));
+        assert_eq!(of(r"(?:I|we) eat"), ("".into(), "".into(), false, false, true));
+        assert_eq!(expression_prefix("I have a/an {int} cuke(s)"), "I have ");
+        assert_eq!(expression_suffix("I have {int} red/green cukes"), " cukes");
+        assert_eq!(expression_suffix("I have {int} cuke(s)"), "");
+        assert_eq!(expression_suffix("a plain step"), "a plain step");
+    }
+
+    #[test]
+    fn test_candidate_pairs() {
+        // distinct literal starts: each pattern is only related to itself, anchored or not
+        let steps: Vec<StepEntry> =
+            (0..2000).flat_map(|i| [regex(&format!(r"^account {i} has (\d+) credits$")), regex(&format!(r"user {i} spends (\d+)"))]).collect();
+        let affixes: Vec<Affixes> = steps.iter().map(Affixes::of).collect();
+        let all: Vec<usize> = (0..steps.len()).collect();
+        let by_prefix = AffixIndex::new(&all, all.len(), |i| affixes[i].prefix.clone());
+        assert!(all.iter().all(|&i| by_prefix.count(i) == 1));
+        assert_eq!(candidate_pairs(&all, &affixes), vec![]);
+        let shared = [regex(r"^I have (\d+)$"), regex(r"^I have (\d+) cukes$"), regex(r"^I (\w+) cukes$"), regex(r"^you have (\d+)$")];
+        let affixes: Vec<Affixes> = shared.iter().map(Affixes::of).collect();
+        assert_eq!(candidate_pairs(&[0, 1, 2, 3], &affixes), vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let steps = [
+            regex(r"^I have (\d+) cukes$"),
+            entry(StepKind::Given, PatternKind::Regex, r"^I have (.*) cukes$", 2),
+            entry(StepKind::Given, PatternKind::Regex, r"^I have (\d+) cukes$", 3),
+            entry(StepKind::When, PatternKind::Regex, r"^I have (\d+) cukes$", 4),
+            entry(StepKind::Given, PatternKind::Regex, r"^done$", 5),
+            // open at opposite ends: always overlapping, not reported
+            entry(StepKind::Given, PatternKind::Regex, r"^I eat", 6),
+            entry(StepKind::Given, PatternKind::Regex, r"apples$", 7),
+            entry(StepKind::Given, PatternKind::Regex, r"^(?:I|we) eat apples$", 8),
+            // unanchored: compared as whole-text patterns
+            entry(StepKind::Given, PatternKind::Regex, r"I own (\d+) pears", 9),
+            entry(StepKind::Given, PatternKind::Regex, r"I own (.*) pears", 10),
+            entry(StepKind::Given, PatternKind::Regex, r"pears", 11),
+        ];
+        let found = overlaps(&steps);
+        assert_eq!(found.len(), 4, "{found:?}");
+        assert_eq!(found[0].kind, StepKind::Given);
+        assert_eq!(found[0].witness, "I have 0 cukes");
+        let lines: Vec<usize> = found[0].steps.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![1, 2, 3]);
+        let pairs: Vec<(Vec<usize>, &str)> = found[1..].iter().map(|o| (o.steps.iter().map(|s| s.line).collect(), o.witness.as_str())).collect();
+        assert_eq!(pairs, vec![(vec![6, 8], "I eat apples"), (vec![7, 8], "I eat apples"), (vec![9, 10], "I own 0 pears")]);
+        let mut reversed = steps.clone();
+        reversed.reverse();
+        assert_eq!(overlaps(&reversed), found);
+    }
+
+    #[test]
+    fn test_overlaps_sharing_a_line() {
+        let given = |regex| entry(StepKind::Given, PatternKind::Regex, regex, 1);
+        let steps = [given(r"^(\d+) b$"), given(r"^a (\d+)$"), given(r"^a (.*)$"), given(r"^(.*) b$")];
+        let found = overlaps(&steps);
+        let patterns: Vec<Vec<&str>> = found.iter().map(|o| o.steps.iter().map(|s| s.regex.as_str()).collect()).collect();
+        assert_eq!(patterns, vec![vec![r"^(.*) b$", r"^(\d+) b$"], vec![r"^(.*) b$", r"^a (.*)$"], vec![r"^a (.*)$", r"^a (\d+)$"]]);
+        let mut reversed = steps.to_vec();
+        reversed.reverse();
+        assert_eq!(overlaps(&reversed), found);
+    }
+}
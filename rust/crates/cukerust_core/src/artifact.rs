@@ -0,0 +1,256 @@
+//! Loading `step_index.json` artifacts written by other tools or by older versions. Each
+//! entry of `steps`, `warnings` and `parameters` is read on its own: entries that do not
+//! deserialize are left out and reported with their JSON pointer instead of failing the
+//! whole index. Unknown fields are ignored and `stats` are recomputed from the steps.
+//! Artifacts of an older [`SCHEMA_VERSION`] are upgraded first, see [`migrate`].
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::captures::captures_for;
+use crate::step_index::{Capture, PatternKind, StepIndex, SCHEMA_VERSION};
+
+/// An artifact entry left out by [`StepIndex::load_artifact`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RejectedEntry {
+    /// JSON pointer (RFC 6901) to the entry, e.g. `/steps/3`.
+    pub pointer: String,
+    /// Why it was rejected, e.g. ``unknown variant `Gvien`, expected one of `Given`, `When`, `Then` ``.
+    pub reason: String,
+}
+
+/// The index read from an artifact, with the entries left out of it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LoadedArtifact {
+    pub index: StepIndex,
+    /// `schema_version` of the artifact before it was migrated.
+    pub from_version: u32,
+    #[serde(default)]
+    pub rejected: Vec<RejectedEntry>,
+}
+
+/// Why an artifact could not be loaded at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactError {
+    /// The text is not valid JSON.
+    Syntax { line: usize, column: usize, message: String },
+    /// The document is valid JSON but not an object.
+    NotAnObject,
+    /// `schema_version` is not a positive integer.
+    InvalidVersion { found: String },
+    /// The artifact was written for a newer format than [`SCHEMA_VERSION`].
+    UnsupportedVersion { found: u32 },
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactError::Syntax { line, column, message } => write!(f, "invalid JSON at line {line}, column {column}: {message}"),
+            ArtifactError::NotAnObject => write!(f, "a Step Index artifact must be a JSON object"),
+            ArtifactError::InvalidVersion { found } => write!(f, "schema_version must be a positive integer, found {found}"),
+            ArtifactError::UnsupportedVersion { found } => {
+                write!(f, "schema_version {found} is newer than the supported version {SCHEMA_VERSION}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError {}
+
+impl StepIndex {
+    /// Reads a `step_index.json` artifact best-effort: invalid `steps`, `warnings` and
+    /// `parameters` entries are skipped and listed in [`LoadedArtifact::rejected`], and
+    /// `stats` are recomputed from the remaining steps, keeping only the artifact's
+    /// `generated_at`. Fails only when the text is not a JSON object or its
+    /// `schema_version` cannot be migrated.
+    pub fn load_artifact(json: &str) -> Result<LoadedArtifact, ArtifactError> {
+        let doc: Value = serde_json::from_str(json)
+            .map_err(|e| ArtifactError::Syntax { line: e.line(), column: e.column(), message: syntax_message(&e) })?;
+        let Value::Object(mut doc) = doc else {
+            return Err(ArtifactError::NotAnObject);
+        };
+        let from_version = migrate(&mut doc)?;
+        let mut rejected = Vec::new();
+        let steps = list(&mut doc, "steps", &mut rejected);
+        let warnings = list(&mut doc, "warnings", &mut rejected);
+        let parameters = list(&mut doc, "parameters", &mut rejected);
+        let mut index = StepIndex::from_steps(steps);
+        index.warnings = warnings;
+        index.parameters = parameters;
+        // freshness checks compare it with source mtimes: never stamp a loaded artifact
+        index.stats.generated_at = doc.get("stats").and_then(|s| s.get("generated_at")).and_then(Value::as_str).map(str::to_string);
+        Ok(LoadedArtifact { index, from_version, rejected })
+    }
+}
+
+/// Upgrades an artifact document in place to [`SCHEMA_VERSION`] and returns the version it
+/// had; documents without `schema_version` are version 1. Entries are only reshaped, never
+/// validated.
+pub fn migrate(doc: &mut Map<String, Value>) -> Result<u32, ArtifactError> {
+    let version = match doc.get("schema_version") {
+        None | Some(Value::Null) => 1,
+        Some(v) => v
+            .as_u64()
+            .filter(|&v| v >= 1)
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ArtifactError::InvalidVersion { found: v.to_string() })?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(ArtifactError::UnsupportedVersion { found: version });
+    }
+    for upgrade in &MIGRATIONS[version as usize - 1..] {
+        upgrade(doc);
+    }
+    doc.insert("schema_version".into(), SCHEMA_VERSION.into());
+    Ok(version)
+}
+
+/// `MIGRATIONS[n]` upgrades version `n + 1` to `n + 2`.
+const MIGRATIONS: [fn(&mut Map<String, Value>); SCHEMA_VERSION as usize - 1] = [v1_to_v2];
+
+/// Version 1 listed `captures` as bare names. Their spans are taken from the pattern;
+/// when its groups do not line up with the names (say it does not parse), the captures
+/// are dropped rather than given made-up spans.
+fn v1_to_v2(doc: &mut Map<String, Value>) {
+    let Some(Value::Array(steps)) = doc.get_mut("steps") else {
+        return;
+    };
+    for step in steps.iter_mut().filter_map(Value::as_object_mut) {
+        let Some(Value::Array(captures)) = step.get("captures") else {
+            continue;
+        };
+        let Some(names) = captures.iter().map(|c| c.as_str().map(str::to_string)).collect::<Option<Vec<String>>>() else {
+            continue;
+        };
+        let kind: PatternKind = step.get("pattern_kind").and_then(|k| serde_json::from_value(k.clone()).ok()).unwrap_or_default();
+        let groups = step.get("regex").and_then(Value::as_str).and_then(|re| captures_for(re, kind, None)).unwrap_or_default();
+        if groups.len() == names.len() {
+            let captures: Vec<Capture> = groups.into_iter().zip(names).map(|(c, name)| Capture { name: Some(name), ..c }).collect();
+            step.insert("captures".into(), serde_json::to_value(captures).expect("captures serialize"));
+        } else {
+            step.remove("captures");
+        }
+    }
+}
+
+/// Deserializes the array `values` found at `pointer` entry by entry, recording the ones
+/// that fail in `rejected`.
+pub fn entries<T: DeserializeOwned>(pointer: &str, values: Vec<Value>, rejected: &mut Vec<RejectedEntry>) -> Vec<T> {
+    values
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, value)| {
+            serde_json::from_value(value)
+                .map_err(|e| rejected.push(RejectedEntry { pointer: format!("{pointer}/{i}"), reason: e.to_string() }))
+                .ok()
+        })
+        .collect()
+}
+
+/// The entries of the top-level array `key`; a missing or `null` one is empty.
+fn list<T: DeserializeOwned>(doc: &mut Map<String, Value>, key: &str, rejected: &mut Vec<RejectedEntry>) -> Vec<T> {
+    match doc.remove(key) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(values)) => entries(&format!("/{key}"), values, rejected),
+        Some(other) => {
+            rejected.push(RejectedEntry { pointer: format!("/{key}"), reason: format!("expected an array, found {}", type_name(&other)) });
+            Vec::new()
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// serde_json's message without its trailing ` at line L column C`.
+fn syntax_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    match message.rfind(" at line ") {
+        Some(at) => message[..at].to_string(),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step_index::StepKind;
+    use serde_json::json;
+
+    #[test]
+    fn test_load_artifact_skips_invalid_entries() {
+        let json = r#"{
+            "steps": [
+                { "kind": "Given", "regex": "^a$", "file": "src/a.rs", "line": 3 },
+                { "kind": "Gvien", "regex": "^b$", "file": "src/a.rs", "line": 5 },
+                { "kind": "Then", "file": "src/a.rs", "line": 7 },
+                { "kind": "Given", "regex": "^a$", "file": "src/b.rs", "line": 1, "future_field": true }
+            ],
+            "stats": { "total": 99, "by_kind": {}, "ambiguous": 0, "generated_at": "2024-01-01T00:00:00Z" },
+            "warnings": [{ "kind": "Unknown", "file": "src/a.rs", "line": 1, "message": "?" }],
+            "parameters": 5
+        }"#;
+        let loaded = StepIndex::load_artifact(json).unwrap();
+        assert_eq!(loaded.index.steps.len(), 2);
+        assert!(loaded.index.steps.iter().all(|s| s.kind == StepKind::Given));
+        assert_eq!((loaded.index.stats.total, loaded.index.stats.ambiguous), (2, 1));
+        assert_eq!(loaded.index.stats.generated_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+        assert!(loaded.index.warnings.is_empty());
+        let pointers: Vec<&str> = loaded.rejected.iter().map(|r| r.pointer.as_str()).collect();
+        assert_eq!(pointers, vec!["/steps/1", "/steps/2", "/warnings/0", "/parameters"]);
+        assert_eq!(loaded.rejected[0].reason, "unknown variant `Gvien`, expected one of `Given`, `When`, `Then`");
+        assert_eq!(loaded.rejected[1].reason, "missing field `regex`");
+        assert_eq!(loaded.rejected[3].reason, "expected an array, found a number");
+    }
+
+    #[test]
+    fn test_migrate_v1() {
+        let mut doc = json!({
+            "steps": [
+                { "kind": "Given", "regex": "^(\\d+) (\\w+)$", "file": "src/a.rs", "line": 1, "captures": ["n", "what"] },
+                { "kind": "When", "regex": "^eat {int}$", "pattern_kind": "Expression", "file": "src/a.rs", "line": 2, "captures": ["n"] },
+                { "kind": "Then", "regex": "^done$", "file": "src/a.rs", "line": 3, "captures": ["stray"] }
+            ],
+            "stats": { "total": 3, "by_kind": { "Given": 1, "When": 1, "Then": 1 }, "ambiguous": 0 }
+        });
+        let map = doc.as_object_mut().unwrap();
+        assert_eq!(migrate(map), Ok(1));
+        assert_eq!(map["schema_version"], SCHEMA_VERSION);
+        assert_eq!(map["steps"][0]["captures"][1], json!({ "index": 2, "name": "what", "start": 7, "end": 12 }));
+        assert_eq!(map["steps"][1]["captures"], json!([{ "index": 1, "name": "n", "start": 5, "end": 10 }]));
+        // no group to take the span from
+        assert!(map["steps"][2].get("captures").is_none());
+        // migrating again is a no-op
+        let before = doc.clone();
+        assert_eq!(migrate(doc.as_object_mut().unwrap()), Ok(SCHEMA_VERSION));
+        assert_eq!(doc, before);
+        let index: StepIndex = serde_json::from_value(doc).unwrap();
+        assert_eq!(index.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_load_artifact_errors() {
+        assert_eq!(StepIndex::load_artifact("[]").unwrap_err(), ArtifactError::NotAnObject);
+        let err = StepIndex::load_artifact("{\n  \"steps\": [,]\n}").unwrap_err();
+        assert_eq!(err.to_string(), "invalid JSON at line 2, column 13: expected value");
+        let empty = StepIndex::load_artifact("{}").unwrap();
+        assert!(empty.index.steps.is_empty() && empty.rejected.is_empty());
+        assert_eq!(empty.index.stats.generated_at, None);
+        assert_eq!((empty.from_version, empty.index.schema_version), (1, SCHEMA_VERSION));
+        let newer = format!("{{\"schema_version\": {}}}", SCHEMA_VERSION + 1);
+        assert_eq!(StepIndex::load_artifact(&newer).unwrap_err(), ArtifactError::UnsupportedVersion { found: SCHEMA_VERSION + 1 });
+        let err = StepIndex::load_artifact(r#"{"schema_version": "2"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "schema_version must be a positive integer, found \"2\"");
+    }
+}
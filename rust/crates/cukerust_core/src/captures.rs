@@ -0,0 +1,313 @@
+//! Capture metadata for step patterns: regex groups (via `regex-syntax`) or Cucumber
+//! Expression parameters, paired positionally with the step function's parameters.
+
+use regex_syntax::ast::{self, Ast};
+
+use crate::consts::top_level_end;
+use crate::cucumber_expression;
+use crate::lexer::{tokens, Token, TokenKind};
+use crate::step_index::{Capture, DetectorKind, PatternKind, StepEntry, StepWarning, WarningKind};
+
+/// A parameter of a step function: `n: usize` -> (`n`, `usize`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FnParam {
+    pub name: String,
+    pub ty: String,
+}
+
+/// A capture group of a pattern, before pairing with function parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Group {
+    index: usize,
+    name: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+/// Builds capture metadata for a pattern. `params` are the step function's parameters
+/// as returned by [`step_params`]; `None` for builder and macro steps.
+/// Returns `None` when the pattern has no groups or cannot be parsed.
+pub(crate) fn captures_for(pattern: &str, kind: PatternKind, params: Option<&[FnParam]>) -> Option<Vec<Capture>> {
+    let groups = groups(pattern, kind)?;
+    if groups.is_empty() {
+        return None;
+    }
+    Some(
+        groups
+            .into_iter()
+            .map(|g| {
+                let param = params.and_then(|p| p.get(g.index - 1));
+                Capture {
+                    index: g.index,
+                    name: param.map(|p| p.name.clone()).or(g.name),
+                    ty: param.map(|p| p.ty.clone()),
+                    start: g.start,
+                    end: g.end,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Checks that a step function takes one step parameter per capture group. cucumber only
+/// notices the difference when the step runs: missing groups panic, extra groups are dropped.
+pub(crate) fn count_mismatch(entry: &StepEntry, params: &[FnParam]) -> Option<StepWarning> {
+    if entry.unresolved.is_some() {
+        return None;
+    }
+    let groups = groups(&entry.regex, entry.pattern_kind)?.len();
+    if groups == params.len() {
+        return None;
+    }
+    let function = entry.function.as_deref().unwrap_or("the step function");
+    let names: Vec<&str> = params.iter().map(|p| p.name.as_str()).collect();
+    Some(StepWarning {
+        kind: WarningKind::CaptureCountMismatch,
+        file: entry.file.clone(),
+        line: entry.line,
+        function: entry.function.clone(),
+        detector: Some(DetectorKind::Attribute),
+        message: format!(
+            "pattern has {groups} capture group{} but `{function}` takes {} step parameter{}{}",
+            if groups == 1 { "" } else { "s" },
+            params.len(),
+            if params.len() == 1 { "" } else { "s" },
+            if names.is_empty() { String::new() } else { format!(" ({})", names.join(", ")) },
+        ),
+    })
+}
+
+fn groups(pattern: &str, kind: PatternKind) -> Option<Vec<Group>> {
+    match kind {
+        PatternKind::Literal => Some(Vec::new()),
+        PatternKind::Expression => Some(
+            cucumber_expression::parameters(pattern)
+                .ok()?
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| Group { index: i + 1, name: None, start: p.start, end: p.end })
+                .collect(),
+        ),
+        PatternKind::Regex => {
+            let parsed = ast::parse::Parser::new().parse(pattern).ok()?;
+            let mut out = Vec::new();
+            collect_groups(&parsed, &mut out);
+            out.sort_by_key(|g| g.index);
+            Some(out)
+        }
+    }
+}
+
+fn collect_groups(node: &Ast, out: &mut Vec<Group>) {
+    match node {
+        Ast::Group(g) => {
+            let (index, name) = match &g.kind {
+                ast::GroupKind::CaptureIndex(i) => (Some(*i as usize), None),
+                ast::GroupKind::CaptureName { name, .. } => (Some(name.index as usize), Some(name.name.clone())),
+                ast::GroupKind::NonCapturing(_) => (None, None),
+            };
+            if let Some(index) = index {
+                out.push(Group { index, name, start: g.span.start.offset, end: g.span.end.offset });
+            }
+            collect_groups(&g.ast, out);
+        }
+        Ast::Repetition(r) => collect_groups(&r.ast, out),
+        Ast::Alternation(a) => a.asts.iter().for_each(|x| collect_groups(x, out)),
+        Ast::Concat(c) => c.asts.iter().for_each(|x| collect_groups(x, out)),
+        _ => {}
+    }
+}
+
+/// Parses a parameter list (the text between a function's parentheses). A `self`
+/// receiver is kept as a parameter named `self` typed by its own text (`&mut self`).
+pub(crate) fn parse_params(list: &str) -> Vec<FnParam> {
+    split_params(list)
+        .into_iter()
+        .filter_map(|arg| {
+            let Some(colon) = type_colon(arg) else {
+                return (arg.rsplit(['&', ' ']).next() == Some("self")).then(|| FnParam { name: "self".into(), ty: arg.to_string() });
+            };
+            let name = arg[..colon].trim();
+            let name = name.strip_prefix("mut ").unwrap_or(name).trim();
+            Some(FnParam { name: name.to_string(), ty: arg[colon + 1..].trim().to_string() })
+        })
+        .collect()
+}
+
+/// Splits a parameter list at top-level commas, treating `<...>` as nesting (types).
+fn split_params(list: &str) -> Vec<&str> {
+    let bytes = list.as_bytes();
+    let mut out = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'[' | b'{' | b'<' => depth += 1,
+            b'>' if i > 0 && bytes[i - 1] == b'-' => {}
+            b')' | b']' | b'}' | b'>' => depth -= 1,
+            b',' if depth == 0 => {
+                out.push(list[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    out.push(list[start..].trim());
+    out.retain(|a| !a.is_empty());
+    out
+}
+
+/// Parameters of a step function after the opening parenthesis of its signature
+/// (`rest` continues up to the end of the file).
+pub(crate) fn params_after_paren(rest: &str) -> Vec<FnParam> {
+    let end = top_level_end(rest, b')').unwrap_or(rest.len());
+    parse_params(&rest[..end])
+}
+
+/// The parameters that receive captures: all but the leading World and any `&Step`.
+/// The World is the first parameter, so a method step's `self` receiver is the World
+/// (both extractors keep the receiver in `all` for this).
+pub(crate) fn step_params(all: Vec<FnParam>) -> Vec<FnParam> {
+    all.into_iter().skip(1).filter(|p| !is_step_type(&p.ty)).collect()
+}
+
+/// What a step function reads from its `&Step` parameter: `(table, docstring)`, each
+/// `Some(true)` when the body accesses `step.table` / `step.docstring`, `Some(false)` when
+/// it cannot (no `&Step` parameter, or one only used for other fields), and `None` when
+/// the step is handed elsewhere so its use is unknown. `all` includes the World parameter.
+pub(crate) fn step_arg_usage(all: &[FnParam], body: &str) -> (Option<bool>, Option<bool>) {
+    let names: Vec<&str> = all.iter().filter(|p| is_step_type(&p.ty)).map(|p| p.name.as_str()).collect();
+    let (mut table, mut docstring, mut escapes) = (false, false, false);
+    let mut prev: Option<Token> = None;
+    let mut tokens = tokens(body).filter(|t| t.kind != TokenKind::Comment && !body[t.start..t.end].trim().is_empty()).peekable();
+    while let Some(t) = tokens.next() {
+        let preceded_by_path = prev.is_some_and(|p| matches!(&body[p.start..p.end], "." | ":"));
+        prev = Some(t);
+        if t.kind != TokenKind::Ident || preceded_by_path || !names.contains(&&body[t.start..t.end]) {
+            continue;
+        }
+        // `step.field` reads a field; anything else (`helper(step)`, `step.clone()`) hands it on
+        let text = |t: Option<&Token>| t.map_or("", |t| &body[t.start..t.end]);
+        let field = match text(tokens.peek()) {
+            "." => {
+                let dot = tokens.next();
+                let field = tokens.next_if(|t| t.kind == TokenKind::Ident);
+                let is_call = matches!(text(tokens.peek()), "(" | ":");
+                prev = field.or(dot);
+                field.filter(|_| !is_call).map(|f| &body[f.start..f.end])
+            }
+            _ => None,
+        };
+        match field {
+            Some("table") => table = true,
+            Some("docstring") => docstring = true,
+            Some(_) => {}
+            None => escapes = true,
+        }
+    }
+    let usage = |read: bool| if read { Some(true) } else if escapes { None } else { Some(false) };
+    (usage(table), usage(docstring))
+}
+
+/// `&Step`, `&gherkin::Step`, `&cucumber::gherkin::Step`, ...
+fn is_step_type(ty: &str) -> bool {
+    let Some(inner) = ty.trim().strip_prefix('&') else {
+        return false;
+    };
+    let inner = inner.trim_start();
+    let inner = match inner.strip_prefix('\'') {
+        Some(lt) => lt.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_').trim_start(),
+        None => inner,
+    };
+    inner.rsplit("::").next().map(str::trim) == Some("Step")
+}
+
+/// Offset of the `:` separating a parameter pattern from its type (skipping `::`).
+fn type_colon(arg: &str) -> Option<usize> {
+    let bytes = arg.as_bytes();
+    (0..bytes.len()).find(|&i| {
+        bytes[i] == b':' && bytes.get(i + 1) != Some(&b':') && (i == 0 || bytes[i - 1] != b':')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step_index::{self, StepKind};
+
+    fn params(list: &str) -> Option<Vec<FnParam>> {
+        Some(step_params(parse_params(list)))
+    }
+
+    #[test]
+    fn test_regex_groups_with_params() {
+        let p = params("world: &mut CoreWorld, kind: String, step: &cucumber::gherkin::Step, mut n: usize");
+        let caps = captures_for(r"^(Given|When) (?P<count>\d+)(?:x)?$", PatternKind::Regex, p.as_deref()).expect("captures");
+        assert_eq!(
+            caps,
+            vec![
+                Capture { index: 1, name: Some("kind".into()), ty: Some("String".into()), start: 1, end: 13 },
+                Capture { index: 2, name: Some("n".into()), ty: Some("usize".into()), start: 14, end: 28 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_groups_and_names_without_params() {
+        let caps = captures_for(r"^((a)|(?P<b>b))$", PatternKind::Regex, None).expect("captures");
+        let summary: Vec<_> = caps.iter().map(|c| (c.index, c.name.clone())).collect();
+        assert_eq!(summary, vec![(1, None), (2, None), (3, Some("b".into()))]);
+    }
+
+    #[test]
+    fn test_expression_and_literal() {
+        let p = params("w: &mut W, n: i64");
+        let caps = captures_for("I have {int} cuke(s)", PatternKind::Expression, p.as_deref()).expect("captures");
+        assert_eq!(caps, vec![Capture { index: 1, name: Some("n".into()), ty: Some("i64".into()), start: 7, end: 12 }]);
+        assert_eq!(captures_for("a (literal)", PatternKind::Literal, None), None);
+        assert_eq!(captures_for("^(unclosed$", PatternKind::Regex, None), None);
+    }
+
+    fn entry(regex: &str, pattern_kind: PatternKind) -> StepEntry {
+        StepEntry { pattern_kind, line: 3, function: Some("index_contains".into()), ..step_index::entry(StepKind::Then, regex) }
+    }
+
+    #[test]
+    fn test_count_mismatch() {
+        let two = params("w: &mut W, n: usize, extra: String").expect("params");
+        let warning = count_mismatch(&entry(r"^the index contains (\d+) steps$", PatternKind::Regex), &two).expect("warning");
+        assert_eq!(warning.kind, WarningKind::CaptureCountMismatch);
+        assert_eq!(warning.line, 3);
+        assert_eq!(warning.message, "pattern has 1 capture group but `index_contains` takes 2 step parameters (n, extra)");
+        assert_eq!(count_mismatch(&entry(r"^(\d+) and (?:x)?(\w+)$", PatternKind::Regex), &two), None);
+        assert_eq!(count_mismatch(&entry("{int} and {word}", PatternKind::Expression), &two), None);
+        let none = params("w: &mut W").expect("params");
+        assert_eq!(count_mismatch(&entry("a (literal)", PatternKind::Literal), &none), None);
+        assert!(count_mismatch(&entry("{int}", PatternKind::Expression), &none).is_some());
+        assert_eq!(count_mismatch(&entry("^(unclosed$", PatternKind::Regex), &none), None);
+    }
+
+    #[test]
+    fn test_step_arg_usage() {
+        let all = parse_params("w: &mut W, step: &Step, n: usize");
+        let usage = |body: &str| step_arg_usage(&all, body);
+        assert_eq!(usage("{ let t = step.table.as_ref().unwrap(); }"), (Some(true), Some(false)));
+        assert_eq!(usage("{ let d = step\n    .docstring\n    .clone(); }"), (Some(false), Some(true)));
+        assert_eq!(usage("{ println!(\"{}\", step.value); }"), (Some(false), Some(false)));
+        assert_eq!(usage("{ helper(step); w.step.table; // step.docstring\n let s = \"step.table\"; }"), (None, None));
+        assert_eq!(usage("{ helper(step, step.table.as_ref()); }"), (Some(true), None));
+        assert_eq!(usage("{ let s = step.clone(); }"), (None, None));
+        assert_eq!(step_arg_usage(&parse_params("w: &mut W, n: usize"), "{ step.table; }"), (Some(false), Some(false)));
+    }
+
+    #[test]
+    fn test_param_types() {
+        let all = parse_params("w: &mut W, map: HashMap<String, Vec<u8>>, f: fn(u8) -> u8, t: &'a Step,");
+        let tys: Vec<_> = all.iter().map(|p| p.ty.as_str()).collect();
+        assert_eq!(tys, vec!["&mut W", "HashMap<String, Vec<u8>>", "fn(u8) -> u8", "&'a Step"]);
+        assert_eq!(step_params(all).len(), 2);
+        let all = parse_params("&'a mut self, n: usize");
+        assert_eq!(all.iter().map(|p| (p.name.as_str(), p.ty.as_str())).collect::<Vec<_>>(), vec![("self", "&'a mut self"), ("n", "usize")]);
+        assert_eq!(step_params(all).into_iter().map(|p| p.name).collect::<Vec<_>>(), vec!["n"]);
+    }
+}
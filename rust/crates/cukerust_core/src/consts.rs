@@ -0,0 +1,276 @@
+//! Resolution of step patterns given as `const`/`static` string items and `concat!`,
+//! e.g. `#[given(regex = GIVEN_BALANCE)]` or `given!(concat!("^I have ", AMOUNT, "$"))`.
+//!
+//! Items are collected per crate (files sharing the prefix before their `src/`, `tests/`,
+//! `benches/` or `examples/` directory) from all files handed to the extractor.
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::lexer::{tokens, TokenKind};
+use crate::step_index::{map_files, string_literal_at, strip_comments_preserving_strings, LiteralAt, SourceFile};
+
+/// Nesting limit for consts referring to consts (also guards against cycles).
+const MAX_DEPTH: usize = 16;
+
+static CONST_ITEM_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:const|static)\s+([A-Za-z_][A-Za-z0-9_]*)\s*:\s*&\s*(?:'static\s+)?str\s*=")
+        .expect("valid const item regex")
+});
+
+#[derive(Debug, Default)]
+pub(crate) struct ConstTable {
+    /// (crate root, item name) -> (defining file, value expression) pairs; more than one
+    /// distinct value is ambiguous.
+    items: HashMap<(String, String), Vec<(String, String)>>,
+}
+
+impl ConstTable {
+    pub(crate) fn from_files(files: &[SourceFile]) -> Self {
+        let mut table = ConstTable::default();
+        for (sf, items) in files.iter().zip(map_files(files, |sf| file_items(&sf.text))) {
+            table.insert_file(&sf.path, items);
+        }
+        table
+    }
+
+    /// Adds the `(name, value)` items defined by the file at `path`, see [`file_items`].
+    pub(crate) fn insert_file(&mut self, path: &str, items: Vec<(String, String)>) {
+        let root = crate_root(path);
+        for (name, value) in items {
+            self.items.entry((root.to_string(), name)).or_default().push((path.to_string(), value));
+        }
+    }
+
+    /// Drops the items defined by the file at `path`.
+    pub(crate) fn remove_file(&mut self, path: &str) {
+        self.items.retain(|_, defs| {
+            defs.retain(|(file, _)| file != path);
+            !defs.is_empty()
+        });
+    }
+
+    /// Resolves the first argument of a step call or attribute when it is a const path or a
+    /// `concat!`. Returns `None` for anything else (literals are handled by the caller).
+    pub(crate) fn resolve_arg(&self, file: &str, arg: &str) -> Option<Result<String, String>> {
+        let arg = arg.trim();
+        if is_concat(arg) || is_const_path(arg) {
+            Some(self.eval(crate_root(file), arg, 0))
+        } else {
+            None
+        }
+    }
+
+    /// Looks up a const by name (last path segment) in the crate of `file`.
+    #[cfg(feature = "syn")]
+    pub(crate) fn resolve_name(&self, file: &str, name: &str) -> Result<String, String> {
+        self.lookup(crate_root(file), name, 0)
+    }
+
+    fn lookup(&self, root: &str, name: &str, depth: usize) -> Result<String, String> {
+        let mut values: Vec<&str> = self
+            .items
+            .get(&(root.to_string(), name.to_string()))
+            .map(|defs| defs.iter().map(|(_, value)| value.as_str()).collect())
+            .unwrap_or_default();
+        values.sort_unstable();
+        values.dedup();
+        match values.as_slice() {
+            [value] => self.eval(root, value, depth + 1),
+            [] => Err(format!("unknown const `{name}`")),
+            _ => Err(format!("const `{name}` is defined more than once")),
+        }
+    }
+
+    fn eval(&self, root: &str, expr: &str, depth: usize) -> Result<String, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("const nesting too deep at `{expr}`"));
+        }
+        let expr = expr.trim();
+        if !expr.is_empty() {
+            if let LiteralAt::Found(lit, end) = string_literal_at(expr, 0) {
+                if expr[end..].trim().is_empty() {
+                    return Ok(lit);
+                }
+            }
+        }
+        if is_concat(expr) {
+            let Some(open) = expr.find(['(', '[', '{']) else {
+                return Err(format!("cannot evaluate `{expr}`"));
+            };
+            let body = &expr[open + 1..];
+            let close = top_level_end(body, b')').ok_or_else(|| format!("unterminated `{expr}`"))?;
+            let mut out = String::new();
+            for (_, part) in split_top_level_args(&body[..close]) {
+                out.push_str(&self.eval(root, part, depth + 1)?);
+            }
+            return Ok(out);
+        }
+        if is_const_path(expr) {
+            let name = expr.rsplit("::").next().unwrap_or(expr).trim();
+            return self.lookup(root, name, depth);
+        }
+        Err(format!("cannot evaluate `{expr}`"))
+    }
+}
+
+/// The `&str` const and static items of a file: (name, value expression) in file order.
+pub(crate) fn file_items(text: &str) -> Vec<(String, String)> {
+    let stripped = strip_comments_preserving_strings(text);
+    let mut items = Vec::new();
+    for cap in CONST_ITEM_RE.captures_iter(&stripped) {
+        let (Some(m0), Some(name)) = (cap.get(0), cap.get(1)) else {
+            continue;
+        };
+        let rest = &stripped[m0.end()..];
+        let Some(end) = top_level_end(rest, b';') else {
+            continue;
+        };
+        items.push((name.as_str().to_string(), rest[..end].trim().to_string()));
+    }
+    items
+}
+
+/// Prefix of `path` up to (excluding) its last `src`/`tests`/`benches`/`examples` directory.
+pub(crate) fn crate_root(path: &str) -> &str {
+    let mut root = 0;
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        if c == '/' || c == '\\' {
+            if matches!(&path[start..i], "src" | "tests" | "benches" | "examples") {
+                root = start;
+            }
+            start = i + 1;
+        }
+    }
+    &path[..root]
+}
+
+/// `concat!(...)`, optionally path-qualified (`std::concat!`).
+fn is_concat(expr: &str) -> bool {
+    let head = expr.split('!').next().unwrap_or("").trim();
+    expr.contains('!') && (head == "concat" || head.ends_with("::concat"))
+}
+
+/// A (possibly module-qualified) SCREAMING_CASE item path such as `steps::AMOUNT`.
+pub(crate) fn is_const_path(expr: &str) -> bool {
+    let segments: Vec<&str> = expr.split("::").map(str::trim).collect();
+    let Some((last, modules)) = segments.split_last() else {
+        return false;
+    };
+    let ident = |s: &&str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !s.starts_with(|c: char| c.is_ascii_digit());
+    modules.iter().all(ident)
+        && ident(last)
+        && last.starts_with(|c: char| c.is_ascii_uppercase())
+        && !last.chars().any(|c| c.is_ascii_lowercase())
+}
+
+/// Offset of the first `terminator` that is not nested in brackets or string literals,
+/// or of an unbalanced closing bracket.
+pub(crate) fn top_level_end(s: &str, terminator: u8) -> Option<usize> {
+    let mut depth = 0usize;
+    for t in tokens(s) {
+        if t.is_literal() && !t.terminated {
+            return None;
+        }
+        if t.kind != TokenKind::Other {
+            continue;
+        }
+        let b = s.as_bytes()[t.start];
+        match b {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth == 0 => return Some(t.start),
+            b')' | b']' | b'}' => depth -= 1,
+            _ if b == terminator && depth == 0 => return Some(t.start),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits a comma-separated argument list at the top level, returning each non-empty
+/// argument with its byte offset in `s`.
+pub(crate) fn split_top_level_args(s: &str) -> Vec<(usize, &str)> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start <= s.len() {
+        let rest = &s[start..];
+        let end = match top_level_end(rest, b',') {
+            Some(e) if rest.as_bytes()[e] == b',' => e,
+            Some(e) => {
+                push_arg(&mut out, s, start, e);
+                break;
+            }
+            None => rest.len(),
+        };
+        push_arg(&mut out, s, start, end);
+        start += end + 1;
+    }
+    out
+}
+
+fn push_arg<'a>(out: &mut Vec<(usize, &'a str)>, s: &'a str, start: usize, len: usize) {
+    let arg = &s[start..start + len];
+    let lead = arg.len() - arg.trim_start().len();
+    if !arg.trim().is_empty() {
+        out.push((start + lead, arg.trim()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(files: &[(&str, &str)]) -> ConstTable {
+        let files: Vec<SourceFile> = files
+            .iter()
+            .map(|(p, t)| SourceFile { path: (*p).into(), text: (*t).into() })
+            .collect();
+        ConstTable::from_files(&files)
+    }
+
+    #[test]
+    fn test_crate_root() {
+        assert_eq!(crate_root("src/steps/account.rs"), "");
+        assert_eq!(crate_root("crates/payments/tests/steps/x.rs"), "crates/payments/");
+        assert_eq!(crate_root("steps.rs"), "");
+        assert_eq!(crate_root("a\\src\\b.rs"), "a\\");
+    }
+
+    #[test]
+    fn test_resolve_consts_and_concat() {
+        let t = table(&[
+            ("src/common.rs", "pub const AMOUNT: &str = r\"(\\d+)\";\nstatic PREFIX: &'static str = concat!(\"^I have \", AMOUNT);"),
+            ("src/steps.rs", ""),
+        ]);
+        assert_eq!(t.resolve_arg("src/steps.rs", "AMOUNT"), Some(Ok(r"(\d+)".to_string())));
+        assert_eq!(t.resolve_arg("src/steps.rs", "common::PREFIX"), Some(Ok(r"^I have (\d+)".to_string())));
+        assert_eq!(
+            t.resolve_arg("src/steps.rs", "concat!(PREFIX, \" cukes$\",)"),
+            Some(Ok(r"^I have (\d+) cukes$".to_string()))
+        );
+        assert_eq!(t.resolve_arg("src/steps.rs", "s"), None);
+        assert_eq!(t.resolve_arg("src/steps.rs", "\"lit\""), None);
+    }
+
+    #[test]
+    fn test_unresolved_reasons() {
+        let t = table(&[
+            ("a/src/x.rs", "const X: &str = \"a\";"),
+            ("b/src/x.rs", "const X: &str = \"b\"; const X: &str = \"c\"; const LOOP: &str = LOOP; const F: &str = format!(\"x\");"),
+        ]);
+        assert_eq!(t.resolve_arg("a/src/y.rs", "X"), Some(Ok("a".to_string())));
+        assert_eq!(t.resolve_arg("c/src/y.rs", "X"), Some(Err("unknown const `X`".to_string())));
+        assert_eq!(t.resolve_arg("b/src/y.rs", "X"), Some(Err("const `X` is defined more than once".to_string())));
+        assert!(matches!(t.resolve_arg("b/src/y.rs", "LOOP"), Some(Err(_))));
+        assert!(matches!(t.resolve_arg("b/src/y.rs", "F"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_split_top_level_args() {
+        let args = split_top_level_args(r#" regex = concat!("a", "b"), name = "x,y" "#);
+        assert_eq!(args, vec![(1, r#"regex = concat!("a", "b")"#), (28, r#"name = "x,y""#)]);
+    }
+}
@@ -0,0 +1,282 @@
+//! Cucumber Expressions (`I have {int} cuke(s)`) compiled into `regex` crate patterns.
+//!
+//! Supports the built-in parameter types, optional text `(s)`, alternation `a/b`
+//! and `\` escapes. Unknown (custom) parameter types match permissively as `(.*)`
+//! since their regexes are only known at runtime.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExpressionError {
+    /// `{` without a closing `}`.
+    UnterminatedParameter { offset: usize },
+    /// `(` without a closing `)`.
+    UnterminatedOptional { offset: usize },
+    /// `{...}` used inside optional text.
+    ParameterInOptional { offset: usize },
+    /// `a/` or `/b`: an alternation with an empty branch.
+    EmptyAlternative { offset: usize },
+    /// `\` at the very end of the expression.
+    TrailingEscape { offset: usize },
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::UnterminatedParameter { offset } => write!(f, "unterminated parameter at offset {offset}"),
+            ExpressionError::UnterminatedOptional { offset } => write!(f, "unterminated optional text at offset {offset}"),
+            ExpressionError::ParameterInOptional { offset } => write!(f, "parameter inside optional text at offset {offset}"),
+            ExpressionError::EmptyAlternative { offset } => write!(f, "empty alternative at offset {offset}"),
+            ExpressionError::TrailingEscape { offset } => write!(f, "trailing escape at offset {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Whitespace(String),
+    /// Parameter type name and byte span of `{...}` in the expression.
+    Parameter(String, usize, usize),
+    Optional(String),
+    Slash(usize),
+}
+
+/// Compiles a Cucumber Expression into an anchored regex string.
+pub fn to_regex(expr: &str) -> Result<String, ExpressionError> {
+    let tokens = tokenize(expr)?;
+    let mut out = String::from("^");
+    // Alternation binds to the run of text/optional tokens between whitespace or parameters.
+    let mut chunk: Vec<&Token> = Vec::new();
+    for t in &tokens {
+        match t {
+            Token::Whitespace(ws) => {
+                emit_chunk(&chunk, &mut out)?;
+                chunk.clear();
+                out.push_str(&regex::escape(ws));
+            }
+            Token::Parameter(name, ..) => {
+                emit_chunk(&chunk, &mut out)?;
+                chunk.clear();
+                out.push_str(parameter_regex(name));
+            }
+            _ => chunk.push(t),
+        }
+    }
+    emit_chunk(&chunk, &mut out)?;
+    out.push('$');
+    Ok(out)
+}
+
+/// A `{name}` parameter of an expression with its byte span (braces included).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Lists the parameters of an expression in order; each one is a capture group of [`to_regex`].
+pub fn parameters(expr: &str) -> Result<Vec<Parameter>, ExpressionError> {
+    Ok(tokenize(expr)?
+        .into_iter()
+        .filter_map(|t| match t {
+            Token::Parameter(name, start, end) => Some(Parameter { name, start, end }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Regex for a parameter type name, e.g. `int` or `word`.
+pub fn parameter_regex(name: &str) -> &'static str {
+    match name {
+        "int" | "biginteger" | "long" | "short" | "byte" => r"(-?\d+)",
+        "float" | "double" | "bigdecimal" => r"([-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?)",
+        "word" => r"([^\s]+)",
+        "string" => r#"("(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*')"#,
+        _ => r"(.*)",
+    }
+}
+
+fn emit_chunk(chunk: &[&Token], out: &mut String) -> Result<(), ExpressionError> {
+    if !chunk.iter().any(|t| matches!(t, Token::Slash(_))) {
+        for t in chunk {
+            emit_simple(t, out);
+        }
+        return Ok(());
+    }
+    let mut alternatives: Vec<String> = vec![String::new()];
+    for t in chunk {
+        if let Token::Slash(offset) = t {
+            if alternatives.last().is_some_and(|a| a.is_empty()) {
+                return Err(ExpressionError::EmptyAlternative { offset: *offset });
+            }
+            alternatives.push(String::new());
+        } else if let Some(last) = alternatives.last_mut() {
+            emit_simple(t, last);
+        }
+    }
+    if alternatives.last().is_some_and(|a| a.is_empty()) {
+        let offset = chunk.iter().rev().find_map(|t| match t {
+            Token::Slash(o) => Some(*o),
+            _ => None,
+        });
+        return Err(ExpressionError::EmptyAlternative { offset: offset.unwrap_or(0) });
+    }
+    out.push_str("(?:");
+    out.push_str(&alternatives.join("|"));
+    out.push(')');
+    Ok(())
+}
+
+fn emit_simple(t: &Token, out: &mut String) {
+    match t {
+        Token::Text(s) => out.push_str(&regex::escape(s)),
+        Token::Optional(s) => {
+            out.push_str("(?:");
+            out.push_str(&regex::escape(s));
+            out.push_str(")?");
+        }
+        _ => {}
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+    let mut text = String::new();
+    let flush = |text: &mut String, tokens: &mut Vec<Token>| {
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(text)));
+        }
+    };
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, e)) => text.push(e),
+                None => return Err(ExpressionError::TrailingEscape { offset: i }),
+            },
+            '{' => {
+                flush(&mut text, &mut tokens);
+                let mut name = String::new();
+                let mut end = None;
+                for (j, n) in chars.by_ref() {
+                    if n == '}' {
+                        end = Some(j + 1);
+                        break;
+                    }
+                    name.push(n);
+                }
+                let Some(end) = end else {
+                    return Err(ExpressionError::UnterminatedParameter { offset: i });
+                };
+                tokens.push(Token::Parameter(name, i, end));
+            }
+            '(' => {
+                flush(&mut text, &mut tokens);
+                let mut inner = String::new();
+                let mut closed = false;
+                while let Some((j, n)) = chars.next() {
+                    match n {
+                        ')' => {
+                            closed = true;
+                            break;
+                        }
+                        '{' => return Err(ExpressionError::ParameterInOptional { offset: j }),
+                        '\\' => match chars.next() {
+                            Some((_, e)) => inner.push(e),
+                            None => return Err(ExpressionError::TrailingEscape { offset: j }),
+                        },
+                        _ => inner.push(n),
+                    }
+                }
+                if !closed {
+                    return Err(ExpressionError::UnterminatedOptional { offset: i });
+                }
+                tokens.push(Token::Optional(inner));
+            }
+            '/' => {
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Slash(i));
+            }
+            c if c.is_whitespace() => {
+                flush(&mut text, &mut tokens);
+                let mut ws = String::from(c);
+                while let Some(&(_, n)) = chars.peek() {
+                    if !n.is_whitespace() {
+                        break;
+                    }
+                    ws.push(n);
+                    chars.next();
+                }
+                tokens.push(Token::Whitespace(ws));
+            }
+            _ => text.push(c),
+        }
+    }
+    flush(&mut text, &mut tokens);
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    fn matches(expr: &str, text: &str) -> bool {
+        Regex::new(&to_regex(expr).expect("valid expression")).expect("valid regex").is_match(text)
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert!(matches("I have {int} cukes", "I have 42 cukes"));
+        assert!(matches("I have {int} cukes", "I have -1 cukes"));
+        assert!(!matches("I have {int} cukes", "I have many cukes"));
+        assert!(matches("it costs {float}", "it costs 1.5"));
+        assert!(matches("I am {word}", "I am hungry"));
+        assert!(!matches("I am {word}", "I am very hungry"));
+        assert!(matches("I say {string}", "I say \"hello world\""));
+        assert!(matches("I say {string}", "I say 'hi'"));
+        assert!(matches("anything {} goes", "anything at all goes"));
+    }
+
+    #[test]
+    fn test_optional_and_alternation() {
+        assert!(matches("I have {int} cuke(s)", "I have 1 cuke"));
+        assert!(matches("I have {int} cuke(s)", "I have 2 cukes"));
+        assert!(matches("in my belly/stomach", "in my belly"));
+        assert!(matches("in my belly/stomach", "in my stomach"));
+        assert!(!matches("in my belly/stomach", "in my bellystomach"));
+    }
+
+    #[test]
+    fn test_escapes_and_metacharacters() {
+        assert!(matches(r"a \(literal\) paren", "a (literal) paren"));
+        assert!(matches(r"a \{int\}", "a {int}"));
+        assert!(matches("what? 1.0 + 2", "what? 1.0 + 2"));
+        assert!(!matches("what? 1.0", "wha 1x0"));
+    }
+
+    #[test]
+    fn test_parameters_spans() {
+        let params = parameters("I have {int} cuke(s) and {}").expect("valid expression");
+        assert_eq!(
+            params,
+            vec![
+                Parameter { name: "int".into(), start: 7, end: 12 },
+                Parameter { name: "".into(), start: 25, end: 27 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(to_regex("a {int"), Err(ExpressionError::UnterminatedParameter { offset: 2 }));
+        assert_eq!(to_regex("a (b"), Err(ExpressionError::UnterminatedOptional { offset: 2 }));
+        assert_eq!(to_regex("a ({int})"), Err(ExpressionError::ParameterInOptional { offset: 3 }));
+        assert_eq!(to_regex("a/ b"), Err(ExpressionError::EmptyAlternative { offset: 1 }));
+        assert_eq!(to_regex("a\\"), Err(ExpressionError::TrailingEscape { offset: 1 }));
+    }
+}
@@ -0,0 +1,747 @@
+//! Step detectors: what the scanner recognises as a step definition.
+//!
+//! The built-in [`AttributeDetector`], [`MacroDetector`] and [`BuilderDetector`] find
+//! `#[given(...)]` functions, `given!(...)` calls and `.given(...)` builder calls, each
+//! for a configurable set of names. Teams wrapping cucumber in their own helpers can add
+//! names through [`DetectorConfig`] (`step!(Given, "...")`, `#[given_async(...)]`,
+//! `.on_given(...)`) or implement [`StepDetector`] for anything else.
+//!
+//! Attributes and macros are also recognised under the names a file's `use` items give
+//! them (`use cucumber::given as dado;`), and not when imported from an unrelated crate.
+
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::captures::{captures_for, count_mismatch, params_after_paren, step_arg_usage, step_params};
+use crate::consts::{split_top_level_args, top_level_end, ConstTable};
+use crate::docs::doc_comment;
+use crate::imports::Imports;
+use crate::lexer::{tokens, Token};
+use crate::modules::{file_module, InlineMods};
+use crate::step_index::{
+    string_literal_at, strip_comment_tokens, DetectorKind, Extractor, FileSteps, LineIndex, LiteralAt, PatternKind,
+    SourceFile, Span, StepEntry, StepKind, StepWarning, WarningKind,
+};
+
+static FN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub(?:\([^)]+\))?\s+)?(?:async\s+)?fn\s+([A-Za-z_][A-Za-z0-9_]*)\s*\(")
+        .expect("valid function name regex")
+});
+
+/// Finds step definitions in a file.
+///
+/// Implementations usually search [`ScannedFile::stripped`] and build entries with
+/// [`ScannedFile::call_step`]; they append what they find to `out`.
+pub trait StepDetector: Send + Sync {
+    fn detect(&self, file: &ScannedFile<'_>, out: &mut FileSteps);
+}
+
+/// A source file prepared for detectors: comments blanked out, line and module lookups.
+pub struct ScannedFile<'a> {
+    source: &'a SourceFile,
+    stripped: String,
+    consts: &'a ConstTable,
+    module: String,
+    mods: InlineMods,
+    lines: LineIndex<'a>,
+    /// Byte ranges of literals, where detector matches are text rather than code.
+    literals: Vec<Range<usize>>,
+    imports: Imports,
+    /// Crates besides cucumber whose step attributes and macros may be imported.
+    crates: &'a [String],
+}
+
+impl<'a> ScannedFile<'a> {
+    pub(crate) fn new(source: &'a SourceFile, consts: &'a ConstTable, crates: &'a [String]) -> Self {
+        // lexed once: stripping comments leaves every other token where it was
+        let toks: Vec<Token> = tokens(&source.text).collect();
+        let stripped = strip_comment_tokens(&source.text, toks.iter().copied());
+        let literals = toks.iter().filter(|t| t.is_literal()).map(|t| t.start..t.end).collect();
+        ScannedFile {
+            source,
+            consts,
+            module: file_module(&source.path),
+            mods: InlineMods::scan(&stripped),
+            lines: LineIndex::new(&source.text),
+            literals,
+            imports: Imports::scan(&source.text, toks.iter().copied()),
+            crates,
+            stripped,
+        }
+    }
+
+    pub fn source(&self) -> &SourceFile {
+        self.source
+    }
+
+    /// The file text with comments replaced by spaces; byte offsets match the source.
+    pub fn stripped(&self) -> &str {
+        &self.stripped
+    }
+
+    /// Whether byte offset `at` lies inside a string, byte-string or char literal.
+    pub fn in_literal(&self, at: usize) -> bool {
+        let i = self.literals.partition_point(|r| r.end <= at);
+        self.literals.get(i).is_some_and(|r| r.start <= at)
+    }
+
+    /// The name an unqualified attribute or macro `name` refers to, following the file's
+    /// `use` items: `dado` for `use cucumber::given as dado;` is `given`. `None` when it
+    /// is imported from a crate that does not define steps.
+    pub fn resolve<'n>(&'n self, name: &'n str) -> Option<&'n str> {
+        self.imports.resolve(name, self.crates)
+    }
+
+    /// Whether a path starting with `root` (`root::given!`) may name a step attribute or
+    /// macro, see [`resolve`](Self::resolve): cucumber's, a configured crate's, the
+    /// crate's own or an import of one of these.
+    pub fn step_root(&self, root: &str) -> bool {
+        self.imports.step_root(root, self.crates)
+    }
+
+    /// The path a bare `name` is imported from when [`resolve`](Self::resolve) rejects it.
+    fn foreign_path(&self, name: &str) -> Option<String> {
+        self.imports.foreign_path(name, self.crates)
+    }
+
+    /// The [`Span`] of a byte range of the file.
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        self.lines.span(start, end)
+    }
+
+    /// A step defined by a call such as `given!(...)` or `.given(...)`: `name` is the byte
+    /// range of the macro or method name and `open` the offset of the `(` opening its
+    /// arguments. The first argument is the pattern. `None` when the call is never closed
+    /// or has no literal, const or `concat!` pattern.
+    pub fn call_step(&self, kind: StepKind, name: Range<usize>, open: usize) -> Option<StepEntry> {
+        self.call_entry(Some(kind), name, open).ok()
+    }
+
+    /// Like [`call_step`](Self::call_step); with no `kind`, the first argument names it
+    /// (`step!(Given, "...")`) and the pattern is the second.
+    fn call_entry(&self, kind: Option<StepKind>, name: Range<usize>, open: usize) -> Result<StepEntry, Dropped> {
+        let args_start = open + 1;
+        let after = &self.stripped[args_start..];
+        let close = top_level_end(after, b')').ok_or_else(|| unclosed(after))?;
+        let args = &after[..close];
+        let (kind, skip) = match kind {
+            Some(kind) => (kind, 0),
+            None => {
+                let mut parsed = split_top_level_args(args).into_iter();
+                let kind = parsed.next().and_then(|(_, first)| kind_from_arg(first)).ok_or(Dropped::NoStepKind)?;
+                (kind, parsed.next().ok_or(Dropped::NoPattern)?.0)
+            }
+        };
+        let (regex, unresolved, pattern) = call_pattern(&args[skip..], &self.source.path, self.consts).ok_or(Dropped::NoPattern)?;
+        let pattern = args_start + skip + pattern.start..args_start + skip + pattern.end;
+        let captures = if unresolved.is_none() { captures_for(&regex, PatternKind::Regex, None) } else { None };
+        let span = self.lines.span(name.start, args_start + close + 1);
+        Ok(StepEntry {
+            kind,
+            regex,
+            pattern_kind: PatternKind::Regex,
+            file: self.source.path.clone(),
+            line: span.start_line,
+            function: None,
+            module: Some(self.mods.module_at(&self.module, name.start)),
+            captures,
+            tags: None,
+            notes: doc_comment(&self.source.text, span.start_line, span.start_line),
+            extractor: Some(Extractor::Scanner),
+            detector: None,
+            unresolved,
+            invalid: None,
+            span: Some(span),
+            pattern_span: Some(self.lines.span(pattern.start, pattern.end)),
+            function_span: None,
+            expects_table: None,
+            expects_docstring: None,
+        })
+    }
+
+    /// The warning for a dropped step definition written `what` (`` `#[given]` ``,
+    /// `` `given!` ``) starting at byte offset `at`.
+    fn dropped(&self, reason: Dropped, detector: DetectorKind, what: &str, at: usize, function: Option<String>) -> StepWarning {
+        reason.warning(&self.source.path, self.lines.span(at, at).start_line, function, detector, what)
+    }
+}
+
+/// Why a step definition was dropped, reported as a [`StepWarning`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Dropped {
+    /// A string literal runs to the end of the file.
+    Unterminated,
+    /// The attribute or call has no closing bracket.
+    NeverClosed,
+    /// An attribute without an argument list (`#[given]`).
+    NoArguments,
+    /// The first argument of a name without a kind (`step!(Foo, "...")`) is not
+    /// `Given`, `When` or `Then`.
+    NoStepKind,
+    /// No string literal, const or `concat!` argument.
+    NoPattern,
+    /// A step name imported from a crate not known to define steps, with the path it is
+    /// imported from (`my_wrapper::given`).
+    ForeignImport(String),
+}
+
+impl Dropped {
+    pub(crate) fn warning(self, file: &str, line: usize, function: Option<String>, detector: DetectorKind, what: &str) -> StepWarning {
+        let (kind, reason) = match self {
+            Dropped::Unterminated => (WarningKind::UnterminatedLiteral, format!("{what} has a string literal that is never closed")),
+            Dropped::NeverClosed => (WarningKind::MalformedDefinition, format!("{what} is never closed")),
+            Dropped::NoArguments => (WarningKind::MalformedDefinition, format!("{what} has no arguments")),
+            Dropped::NoStepKind => {
+                (WarningKind::MalformedDefinition, format!("the first argument of {what} is not a step kind (Given, When or Then)"))
+            }
+            Dropped::NoPattern => (WarningKind::UnsupportedPattern, format!("{what} has no string literal, const or `concat!` pattern")),
+            Dropped::ForeignImport(path) => {
+                let root = path.split("::").next().unwrap_or_default();
+                let reason = format!("{what} is `{path}`, from a crate not known to define steps (list `{root}` in `cukerust.detectors.crates` if it does)");
+                (WarningKind::UnknownStepCrate, reason)
+            }
+        };
+        StepWarning {
+            kind,
+            file: file.to_string(),
+            line,
+            function,
+            detector: Some(detector),
+            message: format!("{reason}; the step is not indexed"),
+        }
+    }
+}
+
+/// Why a definition whose closing bracket is missing from `rest` (the text after its
+/// opening bracket) was dropped.
+fn unclosed(rest: &str) -> Dropped {
+    if tokens(rest).any(|t| t.is_literal() && !t.terminated) {
+        Dropped::Unterminated
+    } else {
+        Dropped::NeverClosed
+    }
+}
+
+/// Step names a built-in detector recognises, each with its step kind, or `None` when
+/// the kind is given by the first argument (`step!(Given, "...")`).
+#[derive(Clone, Debug)]
+struct Names(Vec<(String, Option<StepKind>)>);
+
+impl Names {
+    fn standard() -> Self {
+        Names(vec![
+            ("given".into(), Some(StepKind::Given)),
+            ("when".into(), Some(StepKind::When)),
+            ("then".into(), Some(StepKind::Then)),
+        ])
+    }
+
+    fn add(&mut self, name: &str, kind: Option<StepKind>) {
+        self.0.retain(|(n, _)| n != name);
+        self.0.push((name.to_string(), kind));
+    }
+
+    fn kind_of(&self, name: &str) -> Option<Option<StepKind>> {
+        self.0.iter().find(|(n, _)| n == name).map(|(_, k)| *k)
+    }
+
+    /// `(name|...)` alternation for building detector regexes.
+    fn alternation(&self) -> String {
+        let names: Vec<String> = self.0.iter().map(|(n, _)| regex::escape(n)).collect();
+        format!("({})", names.join("|"))
+    }
+}
+
+/// Step kind named by an argument: `Given`, `when`, `StepType::Then`, ...
+pub(crate) fn kind_from_arg(arg: &str) -> Option<StepKind> {
+    let last = arg.trim().rsplit("::").next()?.trim();
+    match last.to_ascii_lowercase().as_str() {
+        "given" => Some(StepKind::Given),
+        "when" => Some(StepKind::When),
+        "then" => Some(StepKind::Then),
+        _ => None,
+    }
+}
+
+/// `#[given(...)]`-style attributes on functions.
+#[derive(Clone, Debug)]
+pub struct AttributeDetector {
+    names: Names,
+    re: Regex,
+}
+
+/// `given!(...)`-style macro calls.
+#[derive(Clone, Debug)]
+pub struct MacroDetector {
+    names: Names,
+    re: Regex,
+}
+
+/// `.given(...)`-style builder method calls.
+#[derive(Clone, Debug)]
+pub struct BuilderDetector {
+    names: Names,
+    re: Regex,
+}
+
+impl AttributeDetector {
+    fn new(names: Names) -> Self {
+        let re = Regex::new(r"#\[\s*(?P<path>(?:::\s*)?(?:\w+\s*::\s*)*)(?:r#)?(?P<name>\w+)").expect("valid attribute regex");
+        AttributeDetector { names, re }
+    }
+
+    /// Whether `name` is recognised and, if so, the step kind it defines (`None` when
+    /// the first argument names it).
+    pub fn kind_of(&self, name: &str) -> Option<Option<StepKind>> {
+        self.names.kind_of(name)
+    }
+}
+
+impl MacroDetector {
+    fn new(names: Names) -> Self {
+        let re = Regex::new(r"\b(?P<name>\w+)!\s*\(").expect("valid step macro regex");
+        MacroDetector { names, re }
+    }
+
+    /// See [`AttributeDetector::kind_of`].
+    pub fn kind_of(&self, name: &str) -> Option<Option<StepKind>> {
+        self.names.kind_of(name)
+    }
+}
+
+impl BuilderDetector {
+    fn new(names: Names) -> Self {
+        let re = Regex::new(&format!(r"\.(?P<name>{})\s*(?:::<[^>]+>)?\s*\(", names.alternation())).expect("valid builder chain regex");
+        BuilderDetector { names, re }
+    }
+
+    /// See [`AttributeDetector::kind_of`].
+    pub fn kind_of(&self, name: &str) -> Option<Option<StepKind>> {
+        self.names.kind_of(name)
+    }
+}
+
+impl Default for AttributeDetector {
+    fn default() -> Self {
+        Self::new(Names::standard())
+    }
+}
+
+impl Default for MacroDetector {
+    fn default() -> Self {
+        Self::new(Names::standard())
+    }
+}
+
+impl Default for BuilderDetector {
+    fn default() -> Self {
+        Self::new(Names::standard())
+    }
+}
+
+/// Kind of the step an attribute or macro name defines: unqualified names (no `path::`
+/// before them) go through the file's imports, qualified ones count when the path starts
+/// with a [step root](ScannedFile::step_root). `Err` when it is no step, with the reason
+/// when it is a step name imported from an unknown crate.
+fn kind_of_named(names: &Names, file: &ScannedFile<'_>, cap: &regex::Captures<'_>) -> Result<Option<StepKind>, Option<Dropped>> {
+    let found = cap.name("name").ok_or(None)?;
+    let name = found.as_str();
+    // macros match without their path (a leading repeated group is slow); look back for it
+    let kind = match path_root(&file.stripped()[..found.start()]) {
+        Some(root) if file.step_root(root) => names.kind_of(name),
+        Some(_) => None,
+        None => match file.resolve(name) {
+            Some(resolved) => names.kind_of(resolved),
+            None => return Err(file.foreign_path(name).filter(|_| names.kind_of(name).is_some()).map(Dropped::ForeignImport)),
+        },
+    };
+    kind.ok_or(None)
+}
+
+/// First segment of the path `before` ends with (`cucumber` for `#[::cucumber::`), `None`
+/// when it does not end with `::`.
+fn path_root(before: &str) -> Option<&str> {
+    let mut rest = before.trim_end().strip_suffix("::")?.trim_end();
+    let mut root = "";
+    loop {
+        let head = rest.trim_end_matches(|c: char| c == '_' || c.is_alphanumeric());
+        if head.len() == rest.len() {
+            return Some(root);
+        }
+        root = &rest[head.len()..];
+        match head.trim_end().strip_suffix("::") {
+            Some(more) => rest = more.trim_end(),
+            None => return Some(root),
+        }
+    }
+}
+
+/// Builder chains and macros, possibly wrapped over several lines; the pattern is looked
+/// for within the call's arguments.
+fn detect_calls(names: &Names, re: &Regex, detector: DetectorKind, file: &ScannedFile<'_>, out: &mut FileSteps) {
+    for cap in re.captures_iter(file.stripped()) {
+        let (Some(m0), Some(name)) = (cap.get(0), cap.name("name")) else {
+            continue;
+        };
+        if file.in_literal(m0.start()) {
+            continue;
+        }
+        match kind_of_named(names, file, &cap).and_then(|kind| file.call_entry(kind, name.range(), m0.end() - 1).map_err(Some)) {
+            Ok(entry) => out.steps.push(StepEntry { detector: Some(detector), ..entry }),
+            // `.then(...)` is common on other types (`bool`, futures): only macros warn
+            Err(Some(reason)) if detector == DetectorKind::Macro => {
+                let what = format!("`{}!`", name.as_str());
+                out.warnings.push(file.dropped(reason, detector, &what, name.start(), None));
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+impl StepDetector for MacroDetector {
+    fn detect(&self, file: &ScannedFile<'_>, out: &mut FileSteps) {
+        detect_calls(&self.names, &self.re, DetectorKind::Macro, file, out);
+    }
+}
+
+impl StepDetector for BuilderDetector {
+    fn detect(&self, file: &ScannedFile<'_>, out: &mut FileSteps) {
+        detect_calls(&self.names, &self.re, DetectorKind::Builder, file, out);
+    }
+}
+
+impl StepDetector for AttributeDetector {
+    fn detect(&self, file: &ScannedFile<'_>, out: &mut FileSteps) {
+        let stripped = file.stripped();
+        let lines = &file.lines;
+        for cap in self.re.captures_iter(stripped) {
+            let (Some(m0), Some(name)) = (cap.get(0), cap.name("name")) else {
+                continue;
+            };
+            if file.in_literal(m0.start()) {
+                continue;
+            }
+            // a step name imported from an unknown crate is reported once the function is known
+            let kind = match kind_of_named(&self.names, file, &cap) {
+                Ok(kind) => Ok(kind),
+                Err(Some(reason)) => Err(reason),
+                Err(None) => continue,
+            };
+            let what = || format!("`#[{}]`", name.as_str());
+            // the attribute runs to its closing bracket; its arguments follow the name
+            let inside_start = m0.start() + stripped[m0.start()..].find('[').map_or(0, |i| i + 1);
+            let Some(close) = top_level_end(&stripped[inside_start..], b']') else {
+                let reason = unclosed(&stripped[inside_start..]);
+                out.warnings.push(file.dropped(reason, DetectorKind::Attribute, &what(), m0.start(), None));
+                continue;
+            };
+            let attr_end = inside_start + close + 1;
+            // best-effort function name capture from the next few lines
+            let suffix = &stripped[attr_end..];
+            let scope_len: usize = suffix.split_inclusive('\n').take(4).map(str::len).sum();
+            let fn_cap = FN_RE.captures(suffix).filter(|c| c.get(0).is_some_and(|m| m.start() < scope_len));
+            let function = fn_cap.as_ref().and_then(|c| c.get(1).map(|m| m.as_str().to_string()));
+            let mut report = |reason| {
+                out.warnings.push(file.dropped(reason, DetectorKind::Attribute, &what(), m0.start(), function.clone()));
+            };
+            let kind = match kind {
+                Ok(kind) => kind,
+                Err(reason) => {
+                    report(reason);
+                    continue;
+                }
+            };
+            let after_name = &stripped[name.end()..attr_end - 1];
+            let Some(args_start) = after_name.trim_start().strip_prefix('(').map(|r| attr_end - 1 - r.len()) else {
+                report(Dropped::NoArguments);
+                continue;
+            };
+            let args_len = top_level_end(&stripped[args_start..], b')').unwrap_or(attr_end - 1 - args_start);
+            let args = &stripped[args_start..args_start + args_len];
+            let (kind, skip) = match kind {
+                Some(kind) => (kind, 0),
+                None => {
+                    let mut parsed = split_top_level_args(args).into_iter();
+                    let Some(kind) = parsed.next().and_then(|(_, first)| kind_from_arg(first)) else {
+                        report(Dropped::NoStepKind);
+                        continue;
+                    };
+                    (kind, parsed.next().map_or(args.len(), |(offset, _)| offset))
+                }
+            };
+            let Some((pattern_kind, regex_text, unresolved, pattern)) = attr_pattern(args, skip, &file.source.path, file.consts)
+            else {
+                report(Dropped::NoPattern);
+                continue;
+            };
+            let lineno = lines.span(m0.start(), m0.start()).start_line;
+            let all_params = fn_cap
+                .as_ref()
+                .and_then(|c| c.get(0))
+                .map(|m| params_after_paren(&suffix[m.end()..]));
+            let params = all_params.clone().map(step_params);
+            let function_span = fn_cap
+                .as_ref()
+                .and_then(|c| c.get(1))
+                .map(|m| lines.span(attr_end + m.start(), attr_end + m.end()));
+            let body = fn_cap
+                .as_ref()
+                .and_then(|c| c.get(0))
+                .and_then(|m| fn_body(&suffix[m.end()..]).map(|b| attr_end + m.end() + b.start..attr_end + m.end() + b.end));
+            let def_end = body.as_ref().map_or(attr_end, |b| b.end);
+            let (expects_table, expects_docstring) = match &all_params {
+                Some(all) => step_arg_usage(all, body.map_or("", |b| &stripped[b])),
+                None => (None, None),
+            };
+            let captures = if unresolved.is_none() {
+                captures_for(&regex_text, pattern_kind, params.as_deref())
+            } else {
+                None
+            };
+            let entry = StepEntry {
+                kind,
+                regex: regex_text,
+                pattern_kind,
+                file: file.source.path.clone(),
+                line: lineno,
+                function,
+                module: Some(file.mods.module_at(&file.module, m0.start())),
+                captures,
+                tags: None,
+                notes: doc_comment(&file.source.text, lineno, function_span.map_or(lineno, |s| s.start_line)),
+                extractor: Some(Extractor::Scanner),
+                detector: Some(DetectorKind::Attribute),
+                unresolved,
+                invalid: None,
+                span: Some(lines.span(m0.start(), def_end)),
+                pattern_span: Some(lines.span(args_start + pattern.start, args_start + pattern.end)),
+                function_span,
+                expects_table,
+                expects_docstring,
+            };
+            if let Some(params) = &params {
+                out.warnings.extend(count_mismatch(&entry, params));
+            }
+            out.steps.push(entry);
+        }
+    }
+}
+
+/// Extra step names to recognise, e.g. from the `cukerust.detectors` setting:
+///
+/// ```json
+/// { "macros": [{ "name": "step" }], "attributes": [{ "name": "given_async", "kind": "Given" }] }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DetectorConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<DetectorName>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<DetectorName>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<DetectorName>,
+    /// Crates besides `cucumber` that export step attributes or macros, e.g. a
+    /// proc-macro crate wrapping cucumber's. Step names imported from other crates are
+    /// not steps.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crates: Vec<String>,
+}
+
+/// A macro, attribute or method name and the step kind it defines; without a kind the
+/// first argument names it (`step!(Given, "...")`).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DetectorName {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<StepKind>,
+}
+
+/// The detectors an extraction runs: the three built-in forms plus any custom ones.
+#[derive(Default)]
+pub struct Detectors {
+    pub(crate) attributes: AttributeDetector,
+    pub(crate) macros: MacroDetector,
+    pub(crate) builders: BuilderDetector,
+    pub(crate) crates: Vec<String>,
+    custom: Vec<Box<dyn StepDetector>>,
+}
+
+impl Detectors {
+    /// The built-in detectors extended with the names declared in `config`.
+    pub fn from_config(config: &DetectorConfig) -> Self {
+        let mut attributes = Names::standard();
+        let mut macros = Names::standard();
+        let mut builders = Names::standard();
+        for (names, declared) in [(&mut attributes, &config.attributes), (&mut macros, &config.macros), (&mut builders, &config.methods)] {
+            for d in declared {
+                names.add(&d.name, d.kind);
+            }
+        }
+        Detectors {
+            attributes: AttributeDetector::new(attributes),
+            macros: MacroDetector::new(macros),
+            builders: BuilderDetector::new(builders),
+            crates: config.crates.clone(),
+            custom: Vec::new(),
+        }
+    }
+
+    /// Adds a custom detector, run after the built-in ones.
+    pub fn with_detector(mut self, detector: impl StepDetector + 'static) -> Self {
+        self.custom.push(Box::new(detector));
+        self
+    }
+
+    /// Runs every detector over `file`.
+    pub(crate) fn scan(&self, file: &ScannedFile<'_>) -> FileSteps {
+        let mut out = FileSteps::default();
+        self.builders.detect(file, &mut out);
+        self.macros.detect(file, &mut out);
+        self.attributes.detect(file, &mut out);
+        self.scan_custom(file, &mut out);
+        out
+    }
+
+    /// Runs only the custom detectors, for files the `syn` extractor handled.
+    pub(crate) fn scan_custom(&self, file: &ScannedFile<'_>, out: &mut FileSteps) {
+        for detector in &self.custom {
+            detector.detect(file, out);
+        }
+    }
+
+    #[cfg(feature = "syn")]
+    pub(crate) fn has_custom(&self) -> bool {
+        !self.custom.is_empty()
+    }
+}
+
+/// Pattern of a builder/macro call from its argument list (the text between its parentheses):
+/// the first argument, when it is a string literal, a const or `concat!`.
+/// The second element carries the reason when the argument could not be resolved,
+/// the third the argument's byte range in `args`.
+fn call_pattern(args: &str, file: &str, consts: &ConstTable) -> Option<(String, Option<String>, Range<usize>)> {
+    let arg = match top_level_end(args, b',') {
+        Some(end) => &args[..end],
+        None => args,
+    };
+    arg_pattern(arg, file, consts)
+}
+
+/// Pattern of an attribute from its argument list: the value of the first argument at or
+/// after byte `skip` (`"..."`, `regex = r"..."`, `expr = CONST`), with its byte range in `args`.
+fn attr_pattern(
+    args: &str,
+    skip: usize,
+    file: &str,
+    consts: &ConstTable,
+) -> Option<(PatternKind, String, Option<String>, Range<usize>)> {
+    let (offset, arg) = split_top_level_args(args).into_iter().find(|(offset, _)| *offset >= skip)?;
+    let start = offset + attr_value_offset(arg);
+    let (text, unresolved, range) = arg_pattern(&args[start..offset + arg.len()], file, consts)?;
+    Some((pattern_kind_for_attr_arg(&args[..start]), text, unresolved, start + range.start..start + range.end))
+}
+
+/// Pattern of a single argument: a string literal making up the whole argument, or the
+/// value of a const or `concat!`. A literal nested deeper (in a closure, a format call)
+/// is not a pattern. The range is that of the trimmed argument within `arg`.
+fn arg_pattern(arg: &str, file: &str, consts: &ConstTable) -> Option<(String, Option<String>, Range<usize>)> {
+    let arg_start = arg.len() - arg.trim_start().len();
+    let arg_range = arg_start..arg_start + arg.trim().len();
+    match consts.resolve_arg(file, arg) {
+        Some(Ok(text)) => Some((text, None, arg_range)),
+        Some(Err(reason)) => Some((arg.trim().to_string(), Some(reason), arg_range)),
+        None => match string_literal_at(arg, arg_start) {
+            LiteralAt::Found(text, end) if end == arg_range.end => Some((text, None, arg_range)),
+            _ => None,
+        },
+    }
+}
+
+/// Body of a function item (braces included), from the text following the `(` of its
+/// signature. A bodiless declaration has an empty body just past its `;`, so `end` is
+/// always the end of the item.
+fn fn_body(rest: &str) -> Option<Range<usize>> {
+    let params_end = top_level_end(rest, b')')?;
+    let tail = &rest[params_end..];
+    let open = params_end + tail.find(['{', ';'])?;
+    if rest.as_bytes()[open] == b';' {
+        return Some(open + 1..open + 1);
+    }
+    top_level_end(&rest[open + 1..], b'}').map(|close| open..open + 1 + close + 1)
+}
+
+/// Offset of the value in a `name = value` attribute argument (0 for bare values).
+fn attr_value_offset(arg: &str) -> usize {
+    match arg.find('=') {
+        Some(eq) if arg[..eq].trim().chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+            let rest = &arg[eq + 1..];
+            eq + 1 + (rest.len() - rest.trim_start().len())
+        }
+        _ => 0,
+    }
+}
+
+/// Classifies an attribute literal by the argument form preceding it:
+/// a bare literal is an exact match, `expr = "..."` a Cucumber Expression,
+/// anything else a regex.
+fn pattern_kind_for_attr_arg(prefix: &str) -> PatternKind {
+    let Some(before_eq) = prefix.trim_end().strip_suffix('=') else {
+        return PatternKind::Literal;
+    };
+    let before_eq = before_eq.trim_end();
+    let ident_start = before_eq
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    match &before_eq[ident_start..] {
+        "expr" => PatternKind::Expression,
+        _ => PatternKind::Regex,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step_index::extract_step_index_from_files;
+
+    /// `Step::then("...")` constructor calls, as some in-house frameworks spell them.
+    struct ThenConstructor;
+
+    impl StepDetector for ThenConstructor {
+        fn detect(&self, file: &ScannedFile<'_>, out: &mut FileSteps) {
+            for (at, _) in file.stripped().match_indices("Step::then(") {
+                if file.in_literal(at) {
+                    continue;
+                }
+                let name = at + "Step::".len()..at + "Step::then".len();
+                out.steps.extend(file.call_step(StepKind::Then, name.clone(), name.end));
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_detector() {
+        let files = vec![SourceFile {
+            path: "src/steps.rs".into(),
+            text: "fn f() {\n    let _ = \"Step::then(\\\"no\\\")\";\n    steps.push(Step::then(r\"^done$\", handler));\n}\n".into(),
+        }];
+        let idx = extract_step_index_from_files(&files, &Detectors::default().with_detector(ThenConstructor));
+        assert_eq!(idx.steps.len(), 1);
+        let step = &idx.steps[0];
+        assert_eq!((step.kind, step.regex.as_str(), step.line), (StepKind::Then, "^done$", 3));
+        assert_eq!(step.pattern_span.map(|s| (s.start_col, s.end_col)), Some((26, 35)));
+        assert!(extract_step_index_from_files(&files, &Detectors::default()).steps.is_empty());
+    }
+
+    #[test]
+    fn test_config_overrides_builtin_kind() {
+        let config = DetectorConfig { macros: vec![DetectorName { name: "given".into(), kind: Some(StepKind::When) }], ..Default::default() };
+        let detectors = Detectors::from_config(&config);
+        assert_eq!(detectors.macros.kind_of("given"), Some(Some(StepKind::When)));
+        assert_eq!(detectors.attributes.kind_of("given"), Some(Some(StepKind::Given)));
+        assert_eq!(detectors.builders.kind_of("on_given"), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Workspace discovery for native consumers (CLIs, language servers, build scripts): walks
+//! a root directory and yields the `.rs` and `.feature` files a Static Scan reads, with the
+//! extension's rules. `.gitignore` files apply at every level (plus `.ignore` and the
+//! repository's `info/exclude`), `target/` directories are skipped, the user's ignore
+//! globs are applied and files over a size limit are left out. Hidden files and
+//! directories are skipped.
+//!
+//! Only built with the `discovery` feature on native targets; the rest of the crate stays
+//! free of I/O.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+use crate::step_index::SourceFile;
+
+/// Files larger than this are left out by default (1 MiB).
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// What to leave out besides ignored files and `target/` directories.
+#[derive(Clone, Debug)]
+pub struct DiscoveryOptions {
+    /// Globs matched against paths relative to the root (`**/vendor/**`, `fixtures/*.rs`),
+    /// like `cukerust.ignoreGlobs`.
+    pub ignore_globs: Vec<String>,
+    /// Files larger than this many bytes are skipped; `None` for no limit.
+    pub max_file_size: Option<u64>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        DiscoveryOptions { ignore_globs: Vec::new(), max_file_size: Some(DEFAULT_MAX_FILE_SIZE) }
+    }
+}
+
+/// Kind of a discovered file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// A `.rs` file, scanned for step definitions.
+    Rust,
+    /// A `.feature` file.
+    Feature,
+}
+
+/// A candidate file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveredFile {
+    pub path: PathBuf,
+    /// Path relative to the root with `/` separators, as used for [`SourceFile::path`].
+    pub relative: String,
+    pub kind: FileKind,
+}
+
+impl DiscoveredFile {
+    /// Reads the file into a [`SourceFile`] keyed by its relative path.
+    pub fn read(&self) -> io::Result<SourceFile> {
+        Ok(SourceFile { path: self.relative.clone(), text: fs::read_to_string(&self.path)? })
+    }
+}
+
+/// A file left out for exceeding [`DiscoveryOptions::max_file_size`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OversizedFile {
+    pub relative: String,
+    pub size: u64,
+}
+
+/// Result of [`discover`], sorted by relative path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Discovery {
+    pub files: Vec<DiscoveredFile>,
+    pub oversized: Vec<OversizedFile>,
+}
+
+impl Discovery {
+    /// Reads every discovered `.rs` file, skipping those that cannot be read as UTF-8 text.
+    pub fn read_rust_sources(&self) -> Vec<SourceFile> {
+        self.files.iter().filter(|f| f.kind == FileKind::Rust).filter_map(|f| f.read().ok()).collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum DiscoveryError {
+    /// The root does not exist or is not a directory.
+    NotADirectory { root: PathBuf },
+    /// An entry of [`DiscoveryOptions::ignore_globs`] is not a valid glob.
+    InvalidGlob { glob: String, message: String },
+}
+
+impl fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscoveryError::NotADirectory { root } => write!(f, "{} is not a directory", root.display()),
+            DiscoveryError::InvalidGlob { glob, message } => write!(f, "invalid ignore glob `{glob}`: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DiscoveryError {}
+
+/// Walks `root` and returns its candidate `.rs` and `.feature` files. Unreadable
+/// directories and entries are skipped.
+pub fn discover(root: &Path, options: &DiscoveryOptions) -> Result<Discovery, DiscoveryError> {
+    if !root.is_dir() {
+        return Err(DiscoveryError::NotADirectory { root: root.to_path_buf() });
+    }
+    let globs = ignore_globs(&options.ignore_globs)?;
+    let walk_root = root.to_path_buf();
+    let walker = WalkBuilder::new(root)
+        // `.gitignore` applies whether or not the root is inside a git checkout
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if is_dir && entry.depth() > 0 && entry.file_name() == "target" {
+                return false;
+            }
+            // directories are matched too, so `vendor/**`-style globs prune whole trees
+            relative(&walk_root, entry.path()).is_none_or(|rel| rel.is_empty() || !globs.is_match(&rel))
+        })
+        .build();
+    let mut out = Discovery::default();
+    for entry in walker.flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let kind = match entry.path().extension().and_then(|e| e.to_str()) {
+            Some("rs") => FileKind::Rust,
+            Some("feature") => FileKind::Feature,
+            _ => continue,
+        };
+        let Some(rel) = relative(root, entry.path()) else {
+            continue;
+        };
+        let size = entry.metadata().map_or(0, |m| m.len());
+        if options.max_file_size.is_some_and(|max| size > max) {
+            out.oversized.push(OversizedFile { relative: rel, size });
+            continue;
+        }
+        out.files.push(DiscoveredFile { path: entry.into_path(), relative: rel, kind });
+    }
+    out.files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    out.oversized.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(out)
+}
+
+fn ignore_globs(globs: &[String]) -> Result<GlobSet, DiscoveryError> {
+    let mut set = GlobSetBuilder::new();
+    for glob in globs {
+        // `*` stays within a path segment, as in VS Code's glob patterns
+        let compiled = GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| DiscoveryError::InvalidGlob { glob: glob.clone(), message: e.kind().to_string() })?;
+        set.add(compiled);
+    }
+    set.build().map_err(|e| DiscoveryError::InvalidGlob { glob: globs.join(", "), message: e.to_string() })
+}
+
+/// `path` relative to `root`, with `/` separators.
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Vec<&str> = rel.components().map(|c| c.as_os_str().to_str()).collect::<Option<_>>()?;
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/discovery/workspace")
+    }
+
+    fn relatives(found: &Discovery) -> Vec<&str> {
+        found.files.iter().map(|f| f.relative.as_str()).collect()
+    }
+
+    #[test]
+    fn test_gitignore_and_target() {
+        let found = discover(&fixture(), &DiscoveryOptions::default()).unwrap();
+        assert_eq!(
+            relatives(&found),
+            vec![
+                "crates/nested/fixtures/keep.rs",
+                "crates/nested/src/lib.rs",
+                "features/login.feature",
+                "src/lib.rs",
+                "src/steps/login.rs",
+                "vendor/dep/src/lib.rs",
+            ]
+        );
+        assert_eq!(found.files.iter().filter(|f| f.kind == FileKind::Feature).count(), 1);
+        assert!(found.oversized.is_empty());
+        let sources = found.read_rust_sources();
+        assert_eq!(sources.len(), 5);
+        assert!(sources.iter().any(|s| s.path == "src/steps/login.rs" && s.text.contains("a logged in user")));
+    }
+
+    #[test]
+    fn test_ignore_globs_and_size_limit() {
+        let options = DiscoveryOptions {
+            ignore_globs: vec!["vendor/**".into(), "**/nested/*/keep.rs".into(), "*.feature".into()],
+            max_file_size: Some(64),
+        };
+        let found = discover(&fixture(), &options).unwrap();
+        // `*` stays within a segment: `*.feature` only matches at the root
+        assert_eq!(relatives(&found), vec!["crates/nested/src/lib.rs", "features/login.feature", "src/lib.rs"]);
+        let oversized: Vec<&str> = found.oversized.iter().map(|f| f.relative.as_str()).collect();
+        assert_eq!(oversized, vec!["src/steps/login.rs"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let missing = fixture().join("missing");
+        assert!(matches!(discover(&missing, &DiscoveryOptions::default()), Err(DiscoveryError::NotADirectory { .. })));
+        let options = DiscoveryOptions { ignore_globs: vec!["src/[".into()], ..Default::default() };
+        let err = discover(&fixture(), &options).unwrap_err();
+        assert!(err.to_string().starts_with("invalid ignore glob `src/[`"), "{err}");
+    }
+}
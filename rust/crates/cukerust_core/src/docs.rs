@@ -0,0 +1,162 @@
+//! Doc comments of step definitions (`///` lines, `/** */` blocks and `#[doc = "..."]`
+//! attributes), kept as markdown for [`StepEntry::notes`](crate::step_index::StepEntry::notes).
+
+use crate::consts::top_level_end;
+use crate::step_index::{string_literal_at, LiteralAt};
+
+/// Doc comment of a definition starting on 1-based line `first` and whose item proper
+/// (e.g. the `fn` of an attributed step) starts on line `item`: the docs directly above
+/// `first`, passing over other attributes, plain comments and blank lines, followed by
+/// any docs between the two. Works on the original, unstripped text.
+pub(crate) fn doc_comment(text: &str, first: usize, item: usize) -> Option<String> {
+    // only the lines up to the item matter
+    let lines: Vec<&str> = text.lines().take(first.max(item)).collect();
+    let first = first.saturating_sub(1).min(lines.len());
+    let item = item.saturating_sub(1).clamp(first, lines.len());
+    let mut docs = docs_above(&lines, first, 0, true);
+    docs.extend(docs_above(&lines, item, first, false));
+    join_doc_lines(docs)
+}
+
+/// Doc lines of the pieces of `lines[floor..end]` that end right above `end`, in order.
+/// Walks up from `end` a whole attribute or block comment at a time; with `stop`, ends at
+/// the first piece that is neither docs nor [`Piece::Skip`], else passes over it.
+fn docs_above(lines: &[&str], end: usize, floor: usize, stop: bool) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut end = end;
+    while end > floor {
+        let (start, piece) = piece_ending_at(lines, end - 1);
+        match piece {
+            _ if start < floor => break,
+            Piece::Doc(doc) => pieces.push(doc),
+            Piece::Skip => {}
+            Piece::Other if stop => break,
+            Piece::Other => {}
+        }
+        end = start;
+    }
+    pieces.into_iter().rev().flatten().collect()
+}
+
+/// Joins doc lines into markdown, removing the indentation they share so nested lists
+/// and code blocks keep theirs. `None` when blank.
+fn join_doc_lines(lines: Vec<String>) -> Option<String> {
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()?;
+    let text: Vec<&str> = lines.iter().map(|l| l.get(indent..).unwrap_or("").trim_end()).collect();
+    Some(text.join("\n").trim_matches('\n').to_string())
+}
+
+enum Piece {
+    /// A `///` line (without the space after the slashes), a `/** */` block or a
+    /// `#[doc = "..."]` attribute, with its lines of text.
+    Doc(Vec<String>),
+    /// Between a doc comment and its item without ending it: other attributes, plain
+    /// comments, blank lines.
+    Skip,
+    Other,
+}
+
+/// The piece of source ending on line index `j`, with the index of its first line:
+/// attributes and block comments may span several lines, anything else is one line.
+fn piece_ending_at(lines: &[&str], j: usize) -> (usize, Piece) {
+    let line = lines[j].trim();
+    if let Some(doc) = line.strip_prefix("///").filter(|d| !d.starts_with('/')) {
+        return (j, Piece::Doc(vec![doc.strip_prefix(' ').unwrap_or(doc).to_string()]));
+    }
+    let comment = line.starts_with("//") && !line.starts_with("//!");
+    if line.is_empty() || comment {
+        return (j, Piece::Skip);
+    }
+    let spanning = |open: &str| {
+        let start = (0..=j).rev().find(|&k| lines[k].trim_start().starts_with(open))?;
+        Some((start, lines[start..=j].join("\n").trim().to_string()))
+    };
+    if line.ends_with("*/") {
+        if let Some((start, block)) = spanning("/*").filter(|(_, b)| b.len() >= 4 && b.find("*/") == Some(b.len() - 2)) {
+            return (start, block_comment(&block));
+        }
+    }
+    if line.ends_with(']') {
+        if let Some((start, attr)) = spanning("#[").filter(|(_, a)| top_level_end(&a[2..], b']') == Some(a.len() - 3)) {
+            return (start, doc_attr(&attr).map_or(Piece::Skip, |doc| Piece::Doc(doc.split('\n').map(str::to_string).collect())));
+        }
+    }
+    (j, Piece::Other)
+}
+
+/// A whole `/* ... */` comment: docs for `/** ... */`, whose lines lose a leading `*`
+/// when all of them have one and otherwise the indentation they share; `/*! ... */`
+/// (inner docs) ends the search like `//!`.
+fn block_comment(block: &str) -> Piece {
+    let inner = &block[2..block.len() - 2];
+    if inner.starts_with('!') {
+        return Piece::Other;
+    }
+    let Some(inner) = inner.strip_prefix('*').filter(|i| !i.is_empty() && !i.starts_with('*')) else {
+        return Piece::Skip;
+    };
+    let mut lines: Vec<&str> = inner.lines().collect();
+    let rest = &mut lines[1..];
+    if rest.iter().all(|l| l.trim().is_empty() || l.trim_start().starts_with('*')) {
+        for l in rest.iter_mut().filter(|l| !l.trim().is_empty()) {
+            let text = &l.trim_start()[1..];
+            *l = text.strip_prefix(' ').unwrap_or(text);
+        }
+    } else if let Some(indent) = rest.iter().filter(|l| !l.trim().is_empty()).map(|l| l.len() - l.trim_start().len()).min() {
+        rest.iter_mut().for_each(|l| *l = l.get(indent..).unwrap_or(""));
+    }
+    lines[0] = lines[0].trim_start();
+    let text: Vec<String> = lines.iter().map(|l| l.trim_end().to_string()).collect();
+    let first = text.iter().position(|l| !l.is_empty()).unwrap_or(text.len());
+    let last = text.iter().rposition(|l| !l.is_empty()).map_or(first, |i| i + 1);
+    Piece::Doc(text[first..last].to_vec())
+}
+
+/// Text of a `#[doc = "..."]` attribute, which may span several lines.
+fn doc_attr(attr: &str) -> Option<String> {
+    let inner = attr.strip_prefix("#[")?.strip_suffix(']')?.trim();
+    let value = inner.strip_prefix("doc")?.trim_start().strip_prefix('=')?.trim_start();
+    match string_literal_at(value, 0) {
+        LiteralAt::Found(text, end) if end == value.len() => Some(text),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_comment() {
+        let text = "fn other() {}\n\n/// Adds cukes.\n///\n/// ```\n///     indented\n/// ```\n#[doc = \"More.\"]\n// not docs\n#[given(\"x\")]\n/// After the attribute.\n#[allow(unused)]\nfn x() {}\n";
+        assert_eq!(
+            doc_comment(text, 10, 13).as_deref(),
+            Some("Adds cukes.\n\n```\n    indented\n```\nMore.\nAfter the attribute.")
+        );
+        assert_eq!(doc_comment(text, 1, 1), None);
+        assert_eq!(doc_comment("fn a() {}\n//// rule\n//! inner\ngiven!(\"x\", f);", 4, 4), None);
+        assert_eq!(doc_comment("let r = registry;\n/// Eats.\nr.when(\"^eat$\", f);", 3, 3).as_deref(), Some("Eats."));
+    }
+
+    #[test]
+    fn test_block_docs() {
+        let text = "/// Line.\n/**\n * Adds cukes.\n *\n *     indented\n */\n/* plain */\n#[given(\"x\")]\nfn x() {}\n";
+        assert_eq!(doc_comment(text, 8, 9).as_deref(), Some("Line.\nAdds cukes.\n\n    indented"));
+        let text = "fn other() {}\n/** Eats\n    all of them.\n\n    - fast\n*/\ngiven!(\"^eat$\", f);\n/**/\nwhen!(\"^a$\", f);\n/*! inner */\nthen!(\"^b$\", f);";
+        assert_eq!(doc_comment(text, 7, 7).as_deref(), Some("Eats\nall of them.\n\n- fast"));
+        assert_eq!(doc_comment(text, 9, 9), None);
+        assert_eq!(doc_comment(text, 11, 11), None);
+    }
+
+    #[test]
+    fn test_multi_line_attributes() {
+        let text = "fn other() {}\n/// Counts.\n#[doc = \"First.\n\nSecond.\"]\n#[doc = \"\"]\n#[allow(\n    dead_code,\n    reason = \"]\"\n)]\n#[given(\n    expr = \"{int}\"\n)]\n#[doc =\n    \"After.\"]\nfn counts() {}\n";
+        assert_eq!(doc_comment(text, 11, 16).as_deref(), Some("Counts.\nFirst.\n\nSecond.\n\nAfter."));
+        assert_eq!(doc_comment("let v = [\n    1,\n]\n/// Not cut off.\ngiven!(\"x\", f);", 5, 5).as_deref(), Some("Not cut off."));
+        assert_eq!(doc_comment("/// Cut off.\nlet v = [\n    1,\n];\n#[given(\"x\")]\nfn x() {}", 5, 6), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! `use` items of a file, so renamed step attributes and macros (`use cucumber::given as
+//! dado;`, `use cucumber::{when as on};`) are recognised, and a `given` imported from an
+//! unrelated crate (or named by a path into one, `other::given!`) is not.
+//!
+//! Imports are collected for the whole file regardless of the module they appear in.
+
+use std::collections::HashMap;
+
+use crate::lexer::{Token, TokenKind};
+
+/// Crates whose `given`/`when`/`then` are always cucumber's.
+const STEP_CRATES: [&str; 2] = ["cucumber", "cucumber_rust"];
+
+/// Names brought into scope by the `use` items of a file.
+#[derive(Debug, Default)]
+pub(crate) struct Imports {
+    /// Local name -> full path of the imported item (`dado` -> `cucumber::given`).
+    names: HashMap<String, Vec<String>>,
+    /// Modules declared in the file (`mod steps;`, `mod steps { ... }`), which `use`
+    /// paths may start with.
+    mods: Vec<String>,
+}
+
+impl Imports {
+    /// Collects the imports of `text`, given its tokens.
+    pub(crate) fn scan(text: &str, toks: impl IntoIterator<Item = Token>) -> Self {
+        let mut words = toks
+            .into_iter()
+            .filter(|t| matches!(t.kind, TokenKind::Ident | TokenKind::Other))
+            .map(|t| &text[t.start..t.end])
+            .filter(|t| !t.trim().is_empty());
+        let mut imports = Imports::default();
+        while let Some(word) = words.next() {
+            match word {
+                "use" => {
+                    // only the tokens of the item itself go to the use-tree parser
+                    let item: Vec<&str> = words.by_ref().take_while(|t| *t != ";").collect();
+                    use_tree(&item, 0, &[], &mut imports.names);
+                }
+                "mod" => {
+                    if let Some(name) = words.next().filter(|t| is_ident(t)) {
+                        imports.mods.push(unraw(name).to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        imports
+    }
+
+    /// The name a bare `local` (unqualified attribute or macro) refers to: `local` itself
+    /// when not imported, the original name when imported from cucumber, one of `crates`
+    /// or the crate's own modules, `None` when imported from anywhere else.
+    pub(crate) fn resolve<'a>(&'a self, local: &'a str, crates: &[String]) -> Option<&'a str> {
+        let Some(path) = self.names.get(local) else {
+            return Some(local);
+        };
+        let root = path.first()?;
+        self.known_root(root, crates).then(|| path.last().map_or(local, String::as_str))
+    }
+
+    /// Whether a qualified attribute or macro path starting with `root` (`root::given`)
+    /// may name a step: cucumber, one of `crates`, the crate's own modules, or a name
+    /// imported from one of them (`use cucumber as cuc;`).
+    pub(crate) fn step_root(&self, root: &str, crates: &[String]) -> bool {
+        self.known_root(root, crates) || self.names.get(root).and_then(|p| p.first()).is_some_and(|r| self.known_root(r, crates))
+    }
+
+    /// The path a bare `local` is imported from when [`resolve`](Self::resolve) rejects it,
+    /// e.g. `my_wrapper::given`.
+    pub(crate) fn foreign_path(&self, local: &str, crates: &[String]) -> Option<String> {
+        let path = self.names.get(local)?;
+        (!self.known_root(path.first()?, crates)).then(|| path.join("::"))
+    }
+
+    fn known_root(&self, root: &str, crates: &[String]) -> bool {
+        let local_root = matches!(root, "crate" | "self" | "super") || self.mods.iter().any(|m| m == root);
+        local_root || STEP_CRATES.contains(&root) || crates.iter().any(|c| c == root)
+    }
+}
+
+/// Parses the use tree starting at token `i` under `prefix`, recording its imports;
+/// returns the index of the first token after it.
+fn use_tree(toks: &[&str], mut i: usize, prefix: &[String], out: &mut HashMap<String, Vec<String>>) -> usize {
+    let mut path = prefix.to_vec();
+    // leading `::` (`use ::cucumber::given;`)
+    while toks.get(i) == Some(&":") {
+        i += 1;
+    }
+    loop {
+        match toks.get(i) {
+            Some(&"{") => {
+                i += 1;
+                while i < toks.len() && toks[i] != "}" {
+                    let next = use_tree(toks, i, &path, out);
+                    // stop on anything unexpected rather than loop forever
+                    i = if next == i { next + 1 } else { next };
+                    if toks.get(i) == Some(&",") {
+                        i += 1;
+                    }
+                }
+                return i + 1;
+            }
+            Some(&"*") => return i + 1,
+            Some(t) if is_ident(t) => {
+                path.push(unraw(t).to_string());
+                i += 1;
+                if toks.get(i) == Some(&":") && toks.get(i + 1) == Some(&":") {
+                    i += 2;
+                    continue;
+                }
+                if path.last().is_some_and(|s| s == "self") {
+                    path.pop();
+                }
+                let mut local = path.last().cloned();
+                if toks.get(i) == Some(&"as") {
+                    local = toks.get(i + 1).map(|t| unraw(t).to_string());
+                    i += 2;
+                }
+                if let Some(local) = local.filter(|l| l != "_") {
+                    out.insert(local, path);
+                }
+                return i;
+            }
+            _ => return i,
+        }
+    }
+}
+
+fn is_ident(t: &str) -> bool {
+    t.starts_with(|c: char| c == '_' || c.is_alphabetic()) || t.starts_with("r#")
+}
+
+fn unraw(t: &str) -> &str {
+    t.strip_prefix("r#").unwrap_or(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokens;
+
+    impl Imports {
+        fn scan_text(text: &str) -> Self {
+            Imports::scan(text, tokens(text))
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let imports = Imports::scan_text(
+            "use cucumber::given as dado;\npub(crate) use ::cucumber::{when as on, then, World};\nuse other::{given as g2, x::{self as y}};\n\
+             use proptest::when as when_p;\nuse crate::helpers::step as then2;\nmod helpers;\nuse helpers::given as local;\nuse other::*;\nuse other::r#fn as _;\n",
+        );
+        let crates = vec!["my_steps".to_string()];
+        assert_eq!(imports.resolve("dado", &crates), Some("given"));
+        assert_eq!(imports.resolve("on", &crates), Some("when"));
+        assert_eq!(imports.resolve("then", &crates), Some("then"));
+        assert_eq!(imports.resolve("g2", &crates), None);
+        assert_eq!(imports.resolve("y", &crates), None);
+        assert_eq!(imports.resolve("when_p", &crates), None);
+        assert_eq!(imports.resolve("then2", &crates), Some("step"));
+        assert_eq!(imports.resolve("local", &crates), Some("given"));
+        assert_eq!(imports.resolve("given", &crates), Some("given"));
+        assert_eq!(Imports::scan_text("use my_steps::given;").resolve("given", &crates), Some("given"));
+        assert_eq!(Imports::scan_text("use other_steps::given;").resolve("given", &crates), None);
+        assert_eq!(imports.foreign_path("g2", &crates).as_deref(), Some("other::given"));
+        assert_eq!(imports.foreign_path("dado", &crates), None);
+        assert_eq!(imports.foreign_path("unimported", &crates), None);
+    }
+
+    #[test]
+    fn test_step_root() {
+        let imports = Imports::scan_text("use cucumber as cuc;\nuse crate::steps;\nuse other as my_steps2;\nmod helpers;\n");
+        let crates = vec!["my_steps".to_string()];
+        for root in ["cucumber", "cucumber_rust", "my_steps", "crate", "self", "super", "helpers", "cuc", "steps"] {
+            assert!(imports.step_root(root, &crates), "{root}");
+        }
+        for root in ["other", "other_crate", "my_steps2", ""] {
+            assert!(!imports.step_root(root, &crates), "{root}");
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Incremental Step Index: keeps the steps of every file and re-extracts only what a
+//! file change affects, reporting what changed as a [`StepIndexDelta`].
+//!
+//! A file is re-extracted when its content hash changes, and also when a `&str` const it
+//! may refer to (in the same crate) is added, changed or removed elsewhere.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts::{crate_root, file_items, ConstTable};
+use crate::detectors::Detectors;
+use crate::parameters::CustomParameter;
+use crate::step_index::{
+    extract_file, string_literal_at, Ambiguity, FileSteps, LiteralAt, SourceFile, Stats, StepEntry, StepIndex, StepKind,
+    StepLocation, StepWarning,
+};
+
+/// What an update did to the index. `changed` holds the new version of steps that kept
+/// their kind and pattern but moved or otherwise changed; `stats` are those of the whole
+/// index after the update, without overlaps (see [`StepIndex::find_overlaps`]).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StepIndexDelta {
+    pub added: Vec<StepEntry>,
+    pub removed: Vec<StepEntry>,
+    pub changed: Vec<StepEntry>,
+    pub stats: Stats,
+}
+
+impl StepIndexDelta {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+struct IndexedFile {
+    source: SourceFile,
+    hash: u64,
+    /// `&str` consts the file defines, see [`file_items`].
+    consts: Vec<(String, String)>,
+    found: FileSteps,
+    parameters: Vec<CustomParameter>,
+    /// Whether a step pattern is a const or `concat!`, so the steps depend on other files.
+    uses_consts: bool,
+}
+
+/// Stateful Step Index over a changing set of files, keyed by path.
+///
+/// ```
+/// use cukerust_core::indexer::StepIndexer;
+/// use cukerust_core::step_index::SourceFile;
+///
+/// let mut indexer = StepIndexer::default();
+/// let delta = indexer.upsert_file(SourceFile { path: "src/steps.rs".into(), text: "given!(\"^a$\", f);".into() });
+/// assert_eq!(delta.added.len(), 1);
+/// let delta = indexer.remove_file("src/steps.rs");
+/// assert_eq!(delta.removed.len(), 1);
+/// assert_eq!(indexer.snapshot().stats.total, 0);
+/// ```
+#[derive(Default)]
+pub struct StepIndexer {
+    detectors: Detectors,
+    files: BTreeMap<String, IndexedFile>,
+    consts: ConstTable,
+    stats: StatsCache,
+}
+
+impl StepIndexer {
+    pub fn new(detectors: Detectors) -> Self {
+        StepIndexer { detectors, ..StepIndexer::default() }
+    }
+
+    /// Adds or replaces a file. Unchanged content is a no-op.
+    pub fn upsert_file(&mut self, file: SourceFile) -> StepIndexDelta {
+        self.upsert_files(vec![file])
+    }
+
+    /// Adds or replaces several files at once, e.g. for the initial scan: consts are
+    /// registered before any file is extracted, so each file is extracted once.
+    pub fn upsert_files(&mut self, files: Vec<SourceFile>) -> StepIndexDelta {
+        let mut dirty = BTreeMap::new();
+        let mut const_roots = BTreeSet::new();
+        for source in files {
+            let hash = content_hash(&source.text);
+            if self.files.get(&source.path).is_some_and(|f| f.hash == hash) {
+                continue;
+            }
+            let consts = file_items(&source.text);
+            let previous = self.files.get(&source.path).map(|f| f.consts.as_slice());
+            if previous.unwrap_or_default() != consts.as_slice() {
+                self.consts.remove_file(&source.path);
+                self.consts.insert_file(&source.path, consts.clone());
+                const_roots.insert(crate_root(&source.path).to_string());
+            }
+            let path = source.path.clone();
+            let old = self.files.insert(path.clone(), IndexedFile::pending(source, hash, consts));
+            // a path given twice keeps the steps from before this call
+            dirty.entry(path).or_insert_with(|| old.map(|f| f.found.steps).unwrap_or_default());
+        }
+        self.update(dirty, &const_roots)
+    }
+
+    /// Removes a file; unknown paths are a no-op.
+    pub fn remove_file(&mut self, path: &str) -> StepIndexDelta {
+        let Some(old) = self.files.remove(path) else {
+            return self.delta();
+        };
+        self.stats.remove(&old.found.steps);
+        let mut const_roots = BTreeSet::new();
+        if !old.consts.is_empty() {
+            self.consts.remove_file(path);
+            const_roots.insert(crate_root(path).to_string());
+        }
+        let mut delta = self.update(BTreeMap::new(), &const_roots);
+        delta.removed.extend(old.found.steps);
+        delta
+    }
+
+    /// The steps of one file, in source order.
+    pub fn file_steps(&self, path: &str) -> &[StepEntry] {
+        self.files.get(path).map_or(&[], |f| f.found.steps.as_slice())
+    }
+
+    /// The whole index, as [`extract_step_index_from_files`](crate::step_index::extract_step_index_from_files)
+    /// would build it from the current files.
+    pub fn snapshot(&self) -> StepIndex {
+        let mut index = StepIndex::from_steps(self.steps().cloned().collect());
+        index.warnings = self.warnings();
+        index.parameters = self.parameters();
+        index
+    }
+
+    /// Definition-level warnings of all files, sorted by file and line.
+    pub fn warnings(&self) -> Vec<StepWarning> {
+        let mut warnings: Vec<StepWarning> = self.files.values().flat_map(|f| f.found.warnings.iter().cloned()).collect();
+        warnings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        warnings
+    }
+
+    /// Custom parameter types of all files, in path order.
+    pub fn parameters(&self) -> Vec<CustomParameter> {
+        self.files.values().flat_map(|f| f.parameters.iter().cloned()).collect()
+    }
+
+    fn steps(&self) -> impl Iterator<Item = &StepEntry> {
+        self.files.values().flat_map(|f| &f.found.steps)
+    }
+
+    fn delta(&self) -> StepIndexDelta {
+        StepIndexDelta { stats: self.stats.stats(), ..Default::default() }
+    }
+
+    /// Re-extracts the `dirty` files (with their steps before the change) plus the
+    /// const-using files of crates whose consts changed, diffing old and new steps.
+    fn update(&mut self, mut todo: BTreeMap<String, Vec<StepEntry>>, const_roots: &BTreeSet<String>) -> StepIndexDelta {
+        for (path, file) in &self.files {
+            if file.uses_consts && const_roots.contains(crate_root(path)) && !todo.contains_key(path) {
+                todo.insert(path.clone(), file.found.steps.clone());
+            }
+        }
+        let mut delta = StepIndexDelta::default();
+        for (path, old) in todo {
+            self.stats.remove(&old);
+            let Some(file) = self.files.get_mut(&path) else {
+                continue;
+            };
+            file.found = extract_file(&file.source, &self.consts, &self.detectors);
+            file.uses_consts = uses_consts(&file.source.text, &file.found.steps);
+            self.stats.add(&file.found.steps);
+            diff(old, &file.found.steps, &mut delta);
+        }
+        delta.stats = self.stats.stats();
+        delta
+    }
+}
+
+/// [`Stats`] of the indexed steps, updated file by file so that an update does not
+/// recount the whole index.
+#[derive(Default)]
+struct StatsCache {
+    /// Everything [`Stats::count`] keeps; ambiguities are added by [`StatsCache::stats`].
+    counts: Stats,
+    /// Where the steps of each kind and pattern are defined.
+    defs: HashMap<(StepKind, String), Vec<StepLocation>>,
+    /// The keys of `defs` defined more than once.
+    ambiguous: HashSet<(StepKind, String)>,
+}
+
+impl StatsCache {
+    fn add(&mut self, steps: &[StepEntry]) {
+        for step in steps {
+            self.counts.count(step, true);
+            let key = (step.kind, step.regex.clone());
+            let defs = self.defs.entry(key.clone()).or_default();
+            defs.push(StepLocation::of(step));
+            if defs.len() == 2 {
+                self.ambiguous.insert(key);
+            }
+        }
+    }
+
+    fn remove(&mut self, steps: &[StepEntry]) {
+        for step in steps {
+            self.counts.count(step, false);
+            let key = (step.kind, step.regex.clone());
+            let Some(defs) = self.defs.get_mut(&key) else {
+                continue;
+            };
+            let at = StepLocation::of(step);
+            if let Some(i) = defs.iter().position(|d| *d == at) {
+                defs.remove(i);
+            }
+            match defs.len() {
+                0 => {
+                    self.defs.remove(&key);
+                }
+                1 => {
+                    self.ambiguous.remove(&key);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn stats(&self) -> Stats {
+        let groups = self.ambiguous.iter().map(|key| Ambiguity { kind: key.0, regex: key.1.clone(), steps: self.defs[key].clone() });
+        self.counts.clone().complete(groups)
+    }
+}
+
+impl IndexedFile {
+    /// A file not extracted yet: [`StepIndexer::update`] fills in its steps.
+    fn pending(source: SourceFile, hash: u64, consts: Vec<(String, String)>) -> Self {
+        let parameters = crate::parameters::from_files(std::slice::from_ref(&source));
+        IndexedFile { source, hash, consts, found: FileSteps::default(), parameters, uses_consts: false }
+    }
+}
+
+fn content_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Whether any step's pattern argument is something other than a string literal.
+fn uses_consts(text: &str, steps: &[StepEntry]) -> bool {
+    steps.iter().any(|s| {
+        let Some(arg) = s.pattern_span.and_then(|span| text.get(span.start..span.end)) else {
+            return false;
+        };
+        !matches!(string_literal_at(arg, 0), LiteralAt::Found(_, end) if end == arg.len())
+    })
+}
+
+/// Pairs old and new steps of a file by kind and pattern (in order, for repeats) and
+/// records which were added, removed or changed.
+fn diff(old: Vec<StepEntry>, new: &[StepEntry], delta: &mut StepIndexDelta) {
+    let mut by_key: HashMap<(StepKind, &str), VecDeque<usize>> = HashMap::new();
+    for (i, s) in old.iter().enumerate() {
+        by_key.entry((s.kind, s.regex.as_str())).or_default().push_back(i);
+    }
+    let mut paired = vec![false; old.len()];
+    for s in new {
+        match by_key.get_mut(&(s.kind, s.regex.as_str())).and_then(VecDeque::pop_front) {
+            Some(i) => {
+                paired[i] = true;
+                if old[i] != *s {
+                    delta.changed.push(s.clone());
+                }
+            }
+            None => delta.added.push(s.clone()),
+        }
+    }
+    delta.removed.extend(old.into_iter().zip(paired).filter(|(_, paired)| !paired).map(|(s, _)| s));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step_index::extract_step_index_from_files;
+
+    fn file(path: &str, text: &str) -> SourceFile {
+        SourceFile { path: path.into(), text: text.into() }
+    }
+
+    fn regexes(steps: &[StepEntry]) -> Vec<&str> {
+        steps.iter().map(|s| s.regex.as_str()).collect()
+    }
+
+    #[test]
+    fn test_snapshot_matches_batch_extraction() {
+        let files = vec![
+            file("src/b.rs", "#[given(regex = GREETING)]\nfn hi(w: &mut W) {}\n#[derive(Parameter)]\n#[param(regex = \"red|blue\")]\nstruct Color;"),
+            file("src/a.rs", "pub const GREETING: &str = \"^hello$\";\nwhen!(\"^eat$\", f);\n#[then(\"x (\\\\d+)\")]\nfn t(w: &mut W) {}"),
+        ];
+        let mut indexer = StepIndexer::default();
+        let delta = indexer.upsert_files(files.clone());
+        assert_eq!(delta.added.len(), 3);
+        let batch = extract_step_index_from_files(&files, &Detectors::default());
+        let snapshot = indexer.snapshot();
+        assert_eq!(snapshot.steps, batch.steps);
+        assert_eq!(snapshot.warnings, batch.warnings);
+        assert_eq!(snapshot.parameters.len(), batch.parameters.len());
+        assert_eq!(delta.stats.total, 3);
+    }
+
+    #[test]
+    fn test_deltas() {
+        let mut indexer = StepIndexer::default();
+        indexer.upsert_file(file("src/steps.rs", "given!(\"^a$\", f);\nwhen!(\"^b$\", f);"));
+        assert!(indexer.upsert_file(file("src/steps.rs", "given!(\"^a$\", f);\nwhen!(\"^b$\", f);")).is_empty());
+
+        let delta = indexer.upsert_file(file("src/steps.rs", "\ngiven!(\"^a$\", f);\nthen!(\"^c$\", f);"));
+        assert_eq!(regexes(&delta.added), vec!["^c$"]);
+        assert_eq!(regexes(&delta.removed), vec!["^b$"]);
+        assert_eq!(regexes(&delta.changed), vec!["^a$"]);
+        assert_eq!(delta.changed[0].line, 2);
+        assert_eq!((delta.stats.total, delta.stats.by_kind.when, delta.stats.by_kind.then), (2, 0, 1));
+
+        let delta = indexer.remove_file("src/steps.rs");
+        assert_eq!(delta.removed.len(), 2);
+        assert_eq!(delta.stats.total, 0);
+        assert!(indexer.remove_file("src/steps.rs").is_empty());
+    }
+
+    #[test]
+    fn test_stats_follow_updates() {
+        fn recount(delta: StepIndexDelta, indexer: &StepIndexer) {
+            let strip = |stats: Stats| serde_json::to_value(Stats { generated_at: None, ..stats }).unwrap();
+            assert_eq!(strip(delta.stats), strip(Stats::of(&indexer.snapshot().steps)));
+        }
+        let mut indexer = StepIndexer::default();
+        let files = vec![
+            file("src/a.rs", "given!(\"^a$\", f);\ngiven!(\"^a$\", g);\nwhen!(\"^b$\", f);"),
+            file("src/b.rs", "when!(\"^b$\", f);\nthen!(\"^c$\", f);"),
+        ];
+        recount(indexer.upsert_files(files), &indexer);
+        assert_eq!(indexer.delta().stats.ambiguous, 2);
+        recount(indexer.upsert_file(file("src/a.rs", "given!(\"^a$\", f);\nthen!(\"^c$\", f);")), &indexer);
+        recount(indexer.upsert_file(file("src/c.rs", "then!(\"^c$\", f);")), &indexer);
+        assert_eq!(indexer.delta().stats.ambiguities[0].steps.len(), 3);
+        recount(indexer.remove_file("src/b.rs"), &indexer);
+        let delta = indexer.remove_file("src/a.rs");
+        assert_eq!((delta.stats.total, delta.stats.ambiguous), (1, 0));
+        recount(delta, &indexer);
+        recount(indexer.remove_file("src/c.rs"), &indexer);
+    }
+
+    #[test]
+    fn test_const_change_updates_dependents() {
+        let mut indexer = StepIndexer::default();
+        indexer.upsert_files(vec![
+            file("src/steps.rs", "given!(GREETING, f);\nwhen!(\"^plain$\", f);"),
+            file("src/consts.rs", "pub const GREETING: &str = \"^hello$\";"),
+            file("other/src/steps.rs", "given!(GREETING, f);"),
+        ]);
+        assert_eq!(indexer.file_steps("src/steps.rs")[0].regex, "^hello$");
+        assert!(indexer.file_steps("other/src/steps.rs")[0].unresolved.is_some());
+
+        let delta = indexer.upsert_file(file("src/consts.rs", "pub const GREETING: &str = \"^hi$\";"));
+        assert_eq!(regexes(&delta.added), vec!["^hi$"]);
+        assert_eq!(regexes(&delta.removed), vec!["^hello$"]);
+
+        let delta = indexer.remove_file("src/consts.rs");
+        assert_eq!(regexes(&delta.added), vec!["GREETING"]);
+        assert_eq!(indexer.file_steps("src/steps.rs")[0].unresolved.as_deref(), Some("unknown const `GREETING`"));
+        assert_eq!(indexer.snapshot().steps, extract_step_index_from_files(&[
+            file("other/src/steps.rs", "given!(GREETING, f);"),
+            file("src/steps.rs", "given!(GREETING, f);\nwhen!(\"^plain$\", f);"),
+        ], &Detectors::default()).steps);
+    }
+}
@@ -0,0 +1,234 @@
+//! Token-level Rust lexing: just enough to tell comments, literals and identifiers
+//! apart from the rest of the code, so scanners never look inside the wrong thing.
+//!
+//! Handles nested block comments, string, byte-string and C-string literals (raw or not),
+//! char and byte literals, lifetimes and raw identifiers. Everything else is returned one
+//! character (or run of ASCII whitespace) at a time as [`TokenKind::Other`].
+
+/// What a [`Token`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// `// ...` or `/* ... */` (nested), doc comments included.
+    Comment,
+    /// A string literal: `"..."` or `r#"..."#`.
+    Str,
+    /// A byte-string or C-string literal: `b"..."`, `br"..."`, `c"..."`, `cr"..."`.
+    OtherStr,
+    /// A char or byte literal: `'x'`, `'\''`, `b'"'`.
+    Char,
+    /// `'a`, `'static`, loop labels.
+    Lifetime,
+    /// Identifiers and keywords, including raw identifiers (`r#given`) and the digits,
+    /// suffix and all of number literals.
+    Ident,
+    /// Any other single character: punctuation, whitespace, ...
+    Other,
+}
+
+/// A token: its kind and byte range. `terminated` is false for a literal or block comment
+/// that runs to the end of the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    pub terminated: bool,
+}
+
+impl Token {
+    /// Whether the token is a literal of any kind (string, byte/C string, char).
+    pub(crate) fn is_literal(&self) -> bool {
+        matches!(self.kind, TokenKind::Str | TokenKind::OtherStr | TokenKind::Char)
+    }
+}
+
+/// The tokens of `s`, in order, covering all of it.
+pub(crate) fn tokens(s: &str) -> impl Iterator<Item = Token> + '_ {
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        if i >= s.len() {
+            return None;
+        }
+        let token = token_at(s, i);
+        i = token.end;
+        Some(token)
+    })
+}
+
+/// The token starting at byte offset `i`, which must be a character boundary.
+pub(crate) fn token_at(s: &str, i: usize) -> Token {
+    let bytes = s.as_bytes();
+    let token = |kind, end, terminated| Token { kind, start: i, end, terminated };
+    // fast path: ASCII that cannot start a comment, literal, lifetime or raw identifier
+    match bytes[i] {
+        b'/' | b'"' | b'\'' | b'r' | b'b' | b'c' => {}
+        b if b.is_ascii_alphanumeric() || b == b'_' => return token(TokenKind::Ident, i + ident_len(&s[i..]), true),
+        b if b.is_ascii_whitespace() => {
+            let run = bytes[i..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+            return token(TokenKind::Other, i + run, true);
+        }
+        b if b.is_ascii() => return token(TokenKind::Other, i + 1, true),
+        _ => {}
+    }
+    let rest = &s[i..];
+    if rest.starts_with("//") {
+        return token(TokenKind::Comment, i + rest.find('\n').unwrap_or(rest.len()), true);
+    }
+    if rest.starts_with("/*") {
+        return match block_comment_end(rest) {
+            Some(end) => token(TokenKind::Comment, i + end, true),
+            None => token(TokenKind::Comment, s.len(), false),
+        };
+    }
+    // literal prefixes: r, b, br, c, cr
+    let prefix = ["br", "cr", "b", "c", "r", ""].into_iter().find(|p| {
+        rest.starts_with(p) && matches!(bytes.get(i + p.len()), Some(b'"' | b'#' | b'\''))
+    });
+    if let Some(prefix) = prefix {
+        let raw = prefix.ends_with('r');
+        let kind = if matches!(prefix, "" | "r") { TokenKind::Str } else { TokenKind::OtherStr };
+        let at = i + prefix.len();
+        if raw {
+            if let Some((end, terminated)) = raw_string_end(s, at) {
+                return token(kind, end, terminated);
+            }
+        } else if bytes[at] == b'"' {
+            let (end, terminated) = quoted_end(s, at + 1, b'"');
+            return token(kind, end, terminated);
+        } else if bytes[at] == b'\'' && matches!(prefix, "" | "b") {
+            if let Some(t) = char_or_lifetime(s, at) {
+                return Token { start: i, ..t };
+            }
+        }
+    }
+    // raw identifier
+    if let Some(ident) = rest.strip_prefix("r#") {
+        let len = ident_len(ident);
+        if len > 0 {
+            return token(TokenKind::Ident, i + 2 + len, true);
+        }
+    }
+    let len = ident_len(rest);
+    if len > 0 {
+        return token(TokenKind::Ident, i + len, true);
+    }
+    let c = rest.chars().next().map_or(1, char::len_utf8);
+    token(TokenKind::Other, i + c, true)
+}
+
+/// End (exclusive) of the nested block comment `s` starts with, if it is closed.
+fn block_comment_end(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// End of a raw string whose hashes start at `at` (just past the `r`): `None` when no
+/// `"` follows the hashes, i.e. this is not a raw string.
+fn raw_string_end(s: &str, at: usize) -> Option<(usize, bool)> {
+    let bytes = s.as_bytes();
+    let hashes = bytes[at..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(at + hashes) != Some(&b'"') {
+        return None;
+    }
+    let closing = format!("\"{}", "#".repeat(hashes));
+    let body = at + hashes + 1;
+    Some(match s[body..].find(&closing) {
+        Some(close) => (body + close + closing.len(), true),
+        None => (s.len(), false),
+    })
+}
+
+/// End of a quoted literal whose content starts at `from`, honouring backslash escapes.
+fn quoted_end(s: &str, from: usize, quote: u8) -> (usize, bool) {
+    let bytes = s.as_bytes();
+    let mut j = from;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => j += 2,
+            b if b == quote => return (j + 1, true),
+            _ => j += 1,
+        }
+    }
+    (s.len(), false)
+}
+
+/// A char literal or lifetime starting with the `'` at `at`; `None` for a stray quote.
+fn char_or_lifetime(s: &str, at: usize) -> Option<Token> {
+    let rest = &s[at + 1..];
+    let mut chars = rest.chars();
+    let first = chars.next()?;
+    if first == '\\' {
+        let (end, terminated) = quoted_end(s, at + 1, b'\'');
+        return Some(Token { kind: TokenKind::Char, start: at, end, terminated });
+    }
+    if chars.next() == Some('\'') {
+        return Some(Token { kind: TokenKind::Char, start: at, end: at + 1 + first.len_utf8() + 1, terminated: true });
+    }
+    let len = ident_len(rest);
+    (len > 0).then(|| Token { kind: TokenKind::Lifetime, start: at, end: at + 1 + len, terminated: true })
+}
+
+/// Length of the identifier (or number) at the start of `s`.
+fn ident_len(s: &str) -> usize {
+    s.char_indices()
+        .find(|&(_, c)| !(c == '_' || c.is_alphanumeric()))
+        .map_or(s.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(s: &str) -> Vec<(TokenKind, &str)> {
+        tokens(s)
+            .filter(|t| t.kind != TokenKind::Other)
+            .map(|t| (t.kind, &s[t.start..t.end]))
+            .collect()
+    }
+
+    #[test]
+    fn test_comments_and_literals() {
+        use TokenKind::*;
+        assert_eq!(kinds("/* a /* b */ c */ x"), vec![(Comment, "/* a /* b */ c */"), (Ident, "x")]);
+        assert_eq!(kinds("'\"' '\\'' b'\"' 'a 'static"), vec![(Char, "'\"'"), (Char, "'\\''"), (Char, "b'\"'"), (Lifetime, "'a"), (Lifetime, "'static")]);
+        assert_eq!(
+            kinds(r##"b"x\"" br"y" c"z" cr#"w"# r"v" "u" "##),
+            vec![(OtherStr, r#"b"x\"""#), (OtherStr, r#"br"y""#), (OtherStr, r#"c"z""#), (OtherStr, r##"cr#"w"#"##), (Str, r#"r"v""#), (Str, r#""u""#)]
+        );
+        assert_eq!(kinds("r#given r#\"s\"# bar\"q\" über_1 2u8"), vec![(Ident, "r#given"), (Str, "r#\"s\"#"), (Ident, "bar"), (Str, "\"q\""), (Ident, "über_1"), (Ident, "2u8")]);
+        assert_eq!(kinds("'é' 'ü"), vec![(Char, "'é'"), (Lifetime, "'ü")]);
+    }
+
+    #[test]
+    fn test_other() {
+        let s = "a(\n    b) ";
+        let other: Vec<&str> = tokens(s).filter(|t| t.kind == TokenKind::Other).map(|t| &s[t.start..t.end]).collect();
+        assert_eq!(other, vec!["(", "\n    ", ")", " "]);
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let t: Vec<Token> = tokens("x /* open /* */").collect();
+        assert_eq!(t.last().map(|t| (t.kind, t.terminated)), Some((TokenKind::Comment, false)));
+        let t: Vec<Token> = tokens("r#\"open\" ").collect();
+        assert_eq!(t.last().map(|t| (t.kind, t.terminated)), Some((TokenKind::Str, false)));
+    }
+}
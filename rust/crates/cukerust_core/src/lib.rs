@@ -1,7 +1,24 @@
 //! cukerust_core: Pure Rust algorithms and types used by the CukeRust extension.
 //! Keep this crate platform-agnostic and free of I/O.
 
+pub mod ambiguity;
+pub mod artifact;
+mod captures;
+mod consts;
+pub mod cucumber_expression;
+pub mod detectors;
+#[cfg(all(feature = "discovery", not(target_arch = "wasm32")))]
+pub mod discovery;
+mod docs;
+mod imports;
+pub mod indexer;
+mod lexer;
+mod modules;
+pub mod parameters;
+pub mod schema;
 pub mod step_index;
+#[cfg(feature = "syn")]
+mod syn_extract;
 
 /// Returns the crate version at compile time (useful for debugging).
 pub fn version() -> &'static str {
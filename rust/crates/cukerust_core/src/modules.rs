@@ -0,0 +1,114 @@
+//! Rust module paths of step definitions: the file's module derived from its path
+//! (`src/steps/account.rs` -> `crate::steps::account`) plus inline `mod x { ... }` nesting.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::consts::{crate_root, top_level_end};
+
+static INLINE_MOD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\bmod\s+(?:r#)?([A-Za-z_][A-Za-z0-9_]*)\s*\{").expect("valid inline mod regex")
+});
+
+/// Module path of a source file relative to its crate: `lib.rs`, `main.rs` and the
+/// top-level files of `tests/`, `benches/`, `examples/` and `src/bin/` are crate roots,
+/// `mod.rs` names its directory.
+pub(crate) fn file_module(path: &str) -> String {
+    let root = crate_root(path);
+    let rel = path[root.len()..].replace('\\', "/");
+    let segments: Vec<&str> = rel.split('/').filter(|s| !s.is_empty()).collect();
+    let (in_src, rest) = match segments.split_first() {
+        Some((&"src", ["bin", rest @ ..])) => (false, rest),
+        Some((&"src", rest)) => (true, rest),
+        Some((dir, rest)) if matches!(*dir, "tests" | "benches" | "examples") => (false, rest),
+        _ => (true, segments.as_slice()),
+    };
+    let mut out = String::from("crate");
+    let Some((file, modules)) = rest.split_last() else {
+        return out;
+    };
+    let stem = file.strip_suffix(".rs").unwrap_or(file);
+    let is_root = match modules.len() {
+        0 => !in_src || matches!(stem, "lib" | "main"),
+        // multi-file test/bench/example/bin crates: `tests/name/main.rs`
+        1 => !in_src && stem == "main",
+        _ => false,
+    };
+    if is_root {
+        return out;
+    }
+    for m in modules {
+        out.push_str("::");
+        out.push_str(m);
+    }
+    if stem != "mod" {
+        out.push_str("::");
+        out.push_str(stem);
+    }
+    out
+}
+
+/// Inline `mod name { ... }` blocks of a (comment-stripped) file, for looking up the
+/// module path at a byte offset.
+pub(crate) struct InlineMods {
+    /// (name, body start, body end) in source order.
+    blocks: Vec<(String, usize, usize)>,
+}
+
+impl InlineMods {
+    pub(crate) fn scan(stripped: &str) -> Self {
+        let blocks = INLINE_MOD_RE
+            .captures_iter(stripped)
+            .filter_map(|cap| {
+                let (m0, name) = (cap.get(0)?, cap.get(1)?);
+                let body = &stripped[m0.end()..];
+                let len = top_level_end(body, b'}').unwrap_or(body.len());
+                Some((name.as_str().to_string(), m0.end(), m0.end() + len))
+            })
+            .collect();
+        InlineMods { blocks }
+    }
+
+    /// `file_module` extended with the inline modules enclosing `offset`.
+    pub(crate) fn module_at(&self, file_module: &str, offset: usize) -> String {
+        let mut out = file_module.to_string();
+        for (name, start, end) in &self.blocks {
+            if *start <= offset && offset < *end {
+                out.push_str("::");
+                out.push_str(name);
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_module() {
+        assert_eq!(file_module("src/lib.rs"), "crate");
+        assert_eq!(file_module("src/main.rs"), "crate");
+        assert_eq!(file_module("src/steps.rs"), "crate::steps");
+        assert_eq!(file_module("src/steps/account.rs"), "crate::steps::account");
+        assert_eq!(file_module("crates/app/src/steps/mod.rs"), "crate::steps");
+        assert_eq!(file_module("src/bin/tool.rs"), "crate");
+        assert_eq!(file_module("tests/e2e/main.rs"), "crate");
+        assert_eq!(file_module("tests/bdd.rs"), "crate");
+        assert_eq!(file_module("tests/steps/parsing.rs"), "crate::steps::parsing");
+        assert_eq!(file_module("tests\\steps\\mod.rs"), "crate::steps");
+        assert_eq!(file_module("steps_attr.rs"), "crate::steps_attr");
+    }
+
+    #[test]
+    fn test_inline_mods() {
+        let text = "mod a { fn x() {} mod b { const S: &str = \"}\"; fn y() {} } fn z() {} }\nfn top() {}";
+        let mods = InlineMods::scan(text);
+        let at = |needle: &str| mods.module_at("crate::steps", text.find(needle).expect("needle"));
+        assert_eq!(at("fn x"), "crate::steps::a");
+        assert_eq!(at("fn y"), "crate::steps::a::b");
+        assert_eq!(at("fn z"), "crate::steps::a");
+        assert_eq!(at("fn top"), "crate::steps");
+    }
+}
@@ -0,0 +1,207 @@
+//! Typed step arguments: whether a captured substring parses into the Rust type of the
+//! parameter receiving it, including custom types deriving cucumber's `Parameter`:
+//!
+//! ```ignore
+//! #[derive(Parameter)]
+//! #[param(name = "animal", regex = "cat|dog")]
+//! enum Animal { Cat, Dog }
+//! ```
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::consts::split_top_level_args;
+use crate::step_index::{map_files, string_literal_at, strip_comments_preserving_strings, LiteralAt, SourceFile};
+
+/// A type deriving cucumber's `Parameter`, usable as a step argument type and as a
+/// `{name}` Cucumber Expression parameter.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomParameter {
+    /// Expression parameter name: `#[param(name = ...)]`, else the lowercased type name.
+    pub name: String,
+    /// Rust type name, e.g. `Animal`.
+    pub type_name: String,
+    /// `#[param(regex = ...)]` pattern the argument must match.
+    pub regex: String,
+    pub file: String,
+    pub line: usize,
+}
+
+// An attribute run (string literals and one level of brackets kept intact) followed by an
+// enum or struct item.
+static PARAM_ITEM_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"((?:#\[(?:[^\[\]"]|"(?:[^"\\]|\\.)*"|\[[^\]]*\])*\]\s*)+)(?:pub(?:\([^)]*\))?\s+)?(?:enum|struct)\s+([A-Za-z_][A-Za-z0-9_]*)"#,
+    )
+    .expect("valid parameter item regex")
+});
+static DERIVE_PARAMETER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"#\[\s*derive\s*\([^\]]*\bParameter\b").expect("valid derive regex")
+});
+static PARAM_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"#\[\s*param\s*\(").expect("valid param attribute regex"));
+
+/// Collects the `#[derive(Parameter)]` types of all files, in file order.
+pub(crate) fn from_files(files: &[SourceFile]) -> Vec<CustomParameter> {
+    map_files(files, from_file).into_iter().flatten().collect()
+}
+
+fn from_file(sf: &SourceFile) -> Vec<CustomParameter> {
+    let mut out = Vec::new();
+    // most files never mention the derive; skip lexing them
+    if !sf.text.contains("Parameter") {
+        return out;
+    }
+    let stripped = strip_comments_preserving_strings(&sf.text);
+    for cap in PARAM_ITEM_RE.captures_iter(&stripped) {
+        let (Some(attrs), Some(ident)) = (cap.get(1), cap.get(2)) else {
+            continue;
+        };
+        if !DERIVE_PARAMETER_RE.is_match(attrs.as_str()) {
+            continue;
+        }
+        let Some(open) = PARAM_ATTR_RE.find(attrs.as_str()) else {
+            continue;
+        };
+        let args = param_args(&attrs.as_str()[open.end()..]);
+        let Some(regex) = args.regex else {
+            continue;
+        };
+        let type_name = ident.as_str().to_string();
+        out.push(CustomParameter {
+            name: args.name.unwrap_or_else(|| type_name.to_lowercase()),
+            type_name,
+            regex,
+            file: sf.path.clone(),
+            line: stripped[..attrs.start()].bytes().filter(|&b| b == b'\n').count() + 1,
+        });
+    }
+    out
+}
+
+#[derive(Default)]
+struct ParamArgs {
+    name: Option<String>,
+    regex: Option<String>,
+}
+
+/// `name = "..."` and `regex = "..."` from the text following `#[param(`.
+fn param_args(body: &str) -> ParamArgs {
+    let mut args = ParamArgs::default();
+    let close = crate::consts::top_level_end(body, b')').unwrap_or(body.len());
+    for (_, arg) in split_top_level_args(&body[..close]) {
+        let Some((key, value)) = arg.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let LiteralAt::Found(text, _) = string_literal_at(value, 0) else {
+            continue;
+        };
+        match key.trim() {
+            "name" => args.name = Some(text),
+            "regex" => args.regex = Some(text),
+            _ => {}
+        }
+    }
+    args
+}
+
+/// Checks that `value` parses into the step parameter type `ty`. Returns the warning
+/// message when it does not; types that are not known here are assumed to parse.
+pub fn parse_error(value: &str, ty: &str, custom: &[CustomParameter]) -> Option<String> {
+    let ty = ty.trim();
+    let ty = ty.strip_prefix('&').map(str::trim_start).unwrap_or(ty);
+    if let Some(inner) = generic_arg(ty, "Option") {
+        return if value.is_empty() { None } else { parse_error(value, inner, custom) };
+    }
+    let name = ty.rsplit("::").next().unwrap_or(ty).trim();
+    let ok = match name {
+        "u8" => value.parse::<u8>().is_ok(),
+        "u16" => value.parse::<u16>().is_ok(),
+        "u32" => value.parse::<u32>().is_ok(),
+        "u64" => value.parse::<u64>().is_ok(),
+        "u128" => value.parse::<u128>().is_ok(),
+        "usize" => value.parse::<usize>().is_ok(),
+        "i8" => value.parse::<i8>().is_ok(),
+        "i16" => value.parse::<i16>().is_ok(),
+        "i32" => value.parse::<i32>().is_ok(),
+        "i64" => value.parse::<i64>().is_ok(),
+        "i128" => value.parse::<i128>().is_ok(),
+        "isize" => value.parse::<isize>().is_ok(),
+        "f32" => value.parse::<f32>().is_ok(),
+        "f64" => value.parse::<f64>().is_ok(),
+        "bool" => value.parse::<bool>().is_ok(),
+        "char" => value.chars().count() == 1,
+        _ => match custom.iter().find(|p| p.type_name == name) {
+            Some(p) => Regex::new(&format!("^(?:{})$", p.regex)).map_or(true, |re| re.is_match(value)),
+            None => true,
+        },
+    };
+    if ok {
+        None
+    } else {
+        Some(format!("argument '{value}' cannot be parsed as {name}"))
+    }
+}
+
+/// `T` of `Wrapper<T>` (optionally path-qualified).
+fn generic_arg<'a>(ty: &'a str, wrapper: &str) -> Option<&'a str> {
+    let (head, rest) = ty.split_once('<')?;
+    let head = head.trim();
+    if head != wrapper && !head.ends_with(&format!("::{wrapper}")) {
+        return None;
+    }
+    rest.trim_end().strip_suffix('>')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_types() {
+        assert_eq!(parse_error("5", "usize", &[]), None);
+        assert_eq!(parse_error("five", "usize", &[]), Some("argument 'five' cannot be parsed as usize".into()));
+        assert!(parse_error("-1", "u32", &[]).is_some());
+        assert_eq!(parse_error("-1", "i64", &[]), None);
+        assert_eq!(parse_error("1.5", "f64", &[]), None);
+        assert!(parse_error("1,5", "f64", &[]).is_some());
+        assert_eq!(parse_error("true", "bool", &[]), None);
+        assert!(parse_error("yes", "bool", &[]).is_some());
+        assert_eq!(parse_error("anything", "String", &[]), None);
+        assert_eq!(parse_error("", "Option<usize>", &[]), None);
+        assert!(parse_error("x", "std::option::Option<usize>", &[]).is_some());
+        assert_eq!(parse_error("x", "Unknown", &[]), None);
+    }
+
+    #[test]
+    fn test_custom_parameters() {
+        let files = vec![SourceFile {
+            path: "src/animal.rs".into(),
+            text: r#"
+/// An animal.
+#[derive(Debug, cucumber::Parameter)]
+#[param(regex = "cat|dog|[a-z]+fish", name = "pet")]
+pub enum Animal { Cat, Dog }
+
+#[derive(Parameter)]
+#[param(regex = r"\d+ (?:kg|g)")]
+struct Weight(u32);
+
+#[derive(Debug)]
+#[param(regex = "ignored")]
+enum NotAParameter {}
+"#
+            .into(),
+        }];
+        let params = from_files(&files);
+        let summary: Vec<_> = params.iter().map(|p| (p.name.as_str(), p.type_name.as_str(), p.regex.as_str(), p.line)).collect();
+        assert_eq!(summary, vec![("pet", "Animal", "cat|dog|[a-z]+fish", 3), ("weight", "Weight", r"\d+ (?:kg|g)", 7)]);
+        assert_eq!(parse_error("goldfish", "Animal", &params), None);
+        assert_eq!(parse_error("cow", "crate::Animal", &params), Some("argument 'cow' cannot be parsed as Animal".into()));
+        assert!(parse_error("3 kg", "Weight", &params).is_none());
+    }
+}
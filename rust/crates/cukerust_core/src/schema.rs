@@ -0,0 +1,155 @@
+//! JSON Schema (draft 2020-12) of the `step_index.json` artifact at [`SCHEMA_VERSION`].
+//! `schema/step_index.schema.json` in this crate is its committed copy; a test keeps the
+//! two in sync (`CUKERUST_UPDATE_SCHEMA=1 cargo test --test schema` rewrites the file).
+//!
+//! Objects allow unknown properties, matching the loaders, which ignore them; the schema
+//! test still fails when the extractor writes a key declared nowhere here.
+
+use serde_json::{json, Value};
+
+use crate::step_index::SCHEMA_VERSION;
+
+/// The schema as a JSON document.
+pub fn step_index_schema() -> Value {
+    let count = json!({ "type": "integer", "minimum": 0 });
+    let line = json!({ "type": "integer", "minimum": 1 });
+    let string = json!({ "type": "string" });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "CukeRust Step Index",
+        "description": format!("step_index.json, schema_version {SCHEMA_VERSION}. Artifacts without schema_version are version 1 and are migrated when loaded."),
+        "type": "object",
+        "required": ["schema_version", "steps", "stats"],
+        "properties": {
+            "schema_version": { "const": SCHEMA_VERSION },
+            "steps": { "type": "array", "items": { "$ref": "#/$defs/StepEntry" } },
+            "stats": { "$ref": "#/$defs/Stats" },
+            "warnings": { "type": "array", "items": { "$ref": "#/$defs/StepWarning" } },
+            "parameters": { "type": "array", "items": { "$ref": "#/$defs/CustomParameter" } }
+        },
+        "$defs": {
+            "StepKind": { "enum": ["Given", "When", "Then"] },
+            "PatternKind": { "enum": ["Regex", "Literal", "Expression"] },
+            "Extractor": { "enum": ["Scanner", "Syn"] },
+            "DetectorKind": { "enum": ["Attribute", "Macro", "Builder"] },
+            "WarningKind": {
+                "enum": ["CaptureCountMismatch", "UnterminatedLiteral", "UnsupportedPattern", "MalformedDefinition", "InvalidPattern", "UnknownStepCrate"]
+            },
+            "StepEntry": {
+                "type": "object",
+                "required": ["kind", "regex", "file", "line"],
+                "properties": {
+                    "kind": { "$ref": "#/$defs/StepKind" },
+                    "regex": string,
+                    "pattern_kind": { "$ref": "#/$defs/PatternKind" },
+                    "file": { "type": "string", "description": "Path relative to the workspace root." },
+                    "line": line,
+                    "function": string,
+                    "module": string,
+                    "captures": { "type": "array", "items": { "$ref": "#/$defs/Capture" } },
+                    "tags": { "type": "array", "items": string },
+                    "notes": string,
+                    "extractor": { "$ref": "#/$defs/Extractor" },
+                    "detector": { "$ref": "#/$defs/DetectorKind" },
+                    "unresolved": string,
+                    "invalid": string,
+                    "span": { "$ref": "#/$defs/Span" },
+                    "pattern_span": { "$ref": "#/$defs/Span" },
+                    "function_span": { "$ref": "#/$defs/Span" },
+                    "expects_table": { "type": "boolean" },
+                    "expects_docstring": { "type": "boolean" }
+                }
+            },
+            "Capture": {
+                "type": "object",
+                "required": ["index", "start", "end"],
+                "properties": {
+                    "index": line,
+                    "name": string,
+                    "type": string,
+                    "start": count,
+                    "end": count
+                }
+            },
+            "Span": {
+                "type": "object",
+                "required": ["start", "end", "start_line", "start_col", "end_line", "end_col"],
+                "properties": {
+                    "start": count,
+                    "end": count,
+                    "start_line": line,
+                    "start_col": count,
+                    "end_line": line,
+                    "end_col": count
+                }
+            },
+            "Stats": {
+                "type": "object",
+                "required": ["total", "by_kind", "ambiguous"],
+                "properties": {
+                    "total": count,
+                    "by_kind": {
+                        "type": "object",
+                        "required": ["Given", "When", "Then"],
+                        "properties": { "Given": count, "When": count, "Then": count }
+                    },
+                    "ambiguous": count,
+                    "ambiguities": { "type": "array", "items": { "$ref": "#/$defs/Ambiguity" } },
+                    "by_file": { "type": "object", "additionalProperties": { "$ref": "#/$defs/FileStats" } },
+                    "by_detector": {
+                        "type": "object",
+                        "required": ["Attribute", "Macro", "Builder", "Other"],
+                        "properties": { "Attribute": count, "Macro": count, "Builder": count, "Other": count }
+                    },
+                    "overlaps": { "type": "array", "items": { "$ref": "#/$defs/Overlap" } },
+                    "generated_at": { "type": "string", "description": "RFC 3339 timestamp." }
+                }
+            },
+            "Ambiguity": {
+                "type": "object",
+                "required": ["kind", "regex", "steps"],
+                "properties": {
+                    "kind": { "$ref": "#/$defs/StepKind" },
+                    "regex": string,
+                    "steps": { "type": "array", "items": { "$ref": "#/$defs/StepLocation" } }
+                }
+            },
+            "FileStats": {
+                "type": "object",
+                "required": ["steps", "ambiguous"],
+                "properties": { "steps": count, "ambiguous": count }
+            },
+            "Overlap": {
+                "type": "object",
+                "required": ["kind", "witness", "steps"],
+                "properties": {
+                    "kind": { "$ref": "#/$defs/StepKind" },
+                    "witness": string,
+                    "steps": { "type": "array", "items": { "$ref": "#/$defs/StepLocation" } }
+                }
+            },
+            "StepLocation": {
+                "type": "object",
+                "required": ["regex", "file", "line"],
+                "properties": { "regex": string, "file": string, "line": line, "function": string }
+            },
+            "StepWarning": {
+                "type": "object",
+                "required": ["kind", "file", "line", "message"],
+                "properties": {
+                    "kind": { "$ref": "#/$defs/WarningKind" },
+                    "file": string,
+                    "line": line,
+                    "function": string,
+                    "detector": { "$ref": "#/$defs/DetectorKind" },
+                    "message": string
+                }
+            },
+            "CustomParameter": {
+                "type": "object",
+                "required": ["name", "type_name", "regex", "file", "line"],
+                "properties": { "name": string, "type_name": string, "regex": string, "file": string, "line": line }
+            }
+        }
+    })
+}
@@ -1,28 +1,174 @@
-use regex::Regex;
-use once_cell::sync::Lazy;
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+use crate::consts::ConstTable;
+use crate::detectors::{Detectors, ScannedFile};
+use crate::lexer::{token_at, tokens, Token, TokenKind};
+use crate::parameters::CustomParameter;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum StepKind {
     Given,
     When,
     Then,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// How a step's `regex` text is interpreted when matching feature lines.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum PatternKind {
+    /// A regular expression (`regex = "..."`, builder chains and macros).
+    #[default]
+    Regex,
+    /// An exact step text (`#[given("...")]` without an argument name).
+    Literal,
+    /// A Cucumber Expression (`expr = "..."`), see [`crate::cucumber_expression`].
+    Expression,
+}
+
+/// Which extractor produced a [`StepEntry`].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Extractor {
+    /// Line/regex based scanner over comment-stripped text (always available).
+    Scanner,
+    /// `syn` AST extractor (`syn` cargo feature).
+    Syn,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct StepEntry {
     pub kind: StepKind,
     pub regex: String,
+    #[serde(default)]
+    pub pattern_kind: PatternKind,
     pub file: String,
     pub line: usize,
+    /// Bare function name, e.g. `index_has`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub function: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub captures: Option<Vec<String>>,
+    /// Module containing the definition, e.g. `crate::steps::account`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captures: Option<Vec<Capture>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+    /// Doc comment of the definition, as markdown.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extractor: Option<Extractor>,
+    /// Built-in detector that found the definition; `None` for custom detectors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detector: Option<DetectorKind>,
+    /// Why the pattern (a const or `concat!`) could not be resolved; `regex` then
+    /// holds the argument's source text and the entry never matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unresolved: Option<String>,
+    /// Why the pattern does not compile (see [`StepEntry::pattern_error`]); the entry
+    /// never matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<String>,
+    /// The whole definition: step attribute through the end of its function, or the
+    /// builder/macro call from its name to the closing parenthesis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// The pattern argument as written (literal with quotes, const path or `concat!`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_span: Option<Span>,
+    /// The function name of an attributed step.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function_span: Option<Span>,
+    /// Whether the step function reads `step.table`, i.e. needs a DataTable; `None` when
+    /// unknown (builder/macro steps, or a `&Step` handed on to other code).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expects_table: Option<bool>,
+    /// Like `expects_table`, for `step.docstring`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expects_docstring: Option<bool>,
+}
+
+impl StepEntry {
+    /// Why the pattern cannot be compiled for matching: a regex syntax error (unbalanced
+    /// group, look-around, backreference) or an invalid Cucumber Expression. `None` for
+    /// valid, literal and unresolved patterns.
+    pub fn pattern_error(&self) -> Option<String> {
+        if self.unresolved.is_some() {
+            return None;
+        }
+        match self.pattern_kind {
+            PatternKind::Literal => None,
+            PatternKind::Regex => regex_error(&self.regex).map(|e| format!("invalid regex: {e}")),
+            PatternKind::Expression => match crate::cucumber_expression::to_regex(&self.regex) {
+                Ok(re) => regex_error(&re).map(|e| format!("invalid Cucumber Expression: {e}")),
+                Err(e) => Some(format!("invalid Cucumber Expression: {e}")),
+            },
+        }
+    }
+}
+
+/// The syntax error of a pattern as the `regex` crate parses it, with its offset.
+fn regex_error(pattern: &str) -> Option<String> {
+    let (kind, offset) = match regex_syntax::Parser::new().parse(pattern).err()? {
+        regex_syntax::Error::Parse(e) => (e.kind().to_string(), e.span().start.offset),
+        regex_syntax::Error::Translate(e) => (e.kind().to_string(), e.span().start.offset),
+        e => return Some(e.to_string()),
+    };
+    Some(format!("{kind} at offset {offset}"))
+}
+
+/// A source range: byte offsets into the file text, 1-based lines and 0-based columns
+/// counted in UTF-16 code units (as editors count them). `end` is exclusive.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// Line starts of a text, for turning byte offsets into [`Span`]s.
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        LineIndex { text, starts }
+    }
+
+    pub(crate) fn span(&self, start: usize, end: usize) -> Span {
+        let (start_line, start_col) = self.line_col(start);
+        let (end_line, end_col) = self.line_col(end);
+        Span { start, end, start_line, start_col, end_line, end_col }
+    }
+
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&s| s <= offset).max(1);
+        let start = self.starts[line - 1];
+        let col = self.text.get(start..offset).map_or(offset - start, |s| s.encode_utf16().count());
+        (line, col)
+    }
+}
+
+/// A capture group of a step pattern, paired with the step function parameter receiving it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Capture {
+    /// 1-based group index.
+    pub index: usize,
+    /// Parameter name, else the `(?P<name>...)` group name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Rust type of the parameter, e.g. `usize`.
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+    /// Byte span of the group (or `{parameter}`) within the pattern.
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -30,10 +176,84 @@ pub struct Stats {
     pub total: usize,
     pub by_kind: ByKind,
     pub ambiguous: usize,
+    /// The `(kind, regex)` pairs behind `ambiguous`, with every definition of each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ambiguities: Vec<Ambiguity>,
+    /// Step counts per file (relative path).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_file: BTreeMap<String, FileStats>,
+    #[serde(default)]
+    pub by_detector: ByDetector,
+    /// Same-kind steps with different patterns that match a common step text; only
+    /// filled by [`StepIndex::find_overlaps`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overlaps: Vec<Overlap>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub generated_at: Option<String>,
 }
 
+/// Definitions sharing a kind and pattern; cucumber-rs fails every step text they match
+/// as an ambiguous match.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Ambiguity {
+    pub kind: StepKind,
+    pub regex: String,
+    /// Every definition, sorted by file and line.
+    pub steps: Vec<StepLocation>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileStats {
+    pub steps: usize,
+    /// Steps of the file that are part of an [`Ambiguity`].
+    pub ambiguous: usize,
+}
+
+/// Step counts per [`DetectorKind`].
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct ByDetector {
+    pub attribute: usize,
+    #[serde(rename = "Macro")]
+    pub macros: usize,
+    pub builder: usize,
+    /// Custom detectors, and entries of indexes that do not record their detector.
+    pub other: usize,
+}
+
+/// Definitions of one kind whose different patterns all match [`Overlap::witness`];
+/// cucumber-rs fails such a step text as an ambiguous match.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Overlap {
+    pub kind: StepKind,
+    /// A step text (without the keyword) both patterns match.
+    pub witness: String,
+    /// Every definition of either pattern, sorted by file and line.
+    pub steps: Vec<StepLocation>,
+}
+
+/// Where a step is defined.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StepLocation {
+    pub regex: String,
+    pub file: String,
+    pub line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+impl StepLocation {
+    pub fn of(step: &StepEntry) -> Self {
+        StepLocation { regex: step.regex.clone(), file: step.file.clone(), line: step.line, function: step.function.clone() }
+    }
+
+    /// Orders definitions by file and line, then pattern and function for those sharing
+    /// a line, so [`Ambiguity`] and [`Overlap`] lists do not depend on extraction order.
+    pub(crate) fn sort_key(&self) -> (&str, usize, &str, Option<&str>) {
+        (&self.file, self.line, &self.regex, self.function.as_deref())
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct ByKind {
@@ -42,10 +262,89 @@ pub struct ByKind {
     pub then: usize,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+/// Version of the Step Index format this crate writes. Older artifacts are upgraded by
+/// [`crate::artifact::migrate`]; [`crate::schema::step_index_schema`] describes this one.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StepIndex {
+    /// Format version; artifacts without one are version 1.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub steps: Vec<StepEntry>,
     pub stats: Stats,
+    /// Definition-level problems found while extracting, sorted by file and line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<StepWarning>,
+    /// Custom parameter types (`#[derive(Parameter)]`) available to step arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<CustomParameter>,
+}
+
+impl Default for StepIndex {
+    fn default() -> Self {
+        StepIndex { schema_version: SCHEMA_VERSION, steps: Vec::new(), stats: Stats::default(), warnings: Vec::new(), parameters: Vec::new() }
+    }
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// What a [`StepWarning`] is about.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// The step function takes a different number of step parameters (excluding the
+    /// World and `&Step`) than its pattern has capture groups.
+    CaptureCountMismatch,
+    /// A string literal in a step definition is never closed; the step is dropped.
+    UnterminatedLiteral,
+    /// A step definition has no string literal, const or `concat!` pattern (e.g. a
+    /// variable or `format!`); the step is dropped.
+    UnsupportedPattern,
+    /// A step attribute or macro whose arguments cannot be read: never closed, missing,
+    /// or a first argument that names no step kind. The step is dropped.
+    MalformedDefinition,
+    /// A step's regex or Cucumber Expression does not compile; the step is kept with
+    /// [`StepEntry::invalid`] set and never matches.
+    InvalidPattern,
+    /// A step attribute or macro is imported from a crate that is neither cucumber nor in
+    /// [`DetectorConfig::crates`](crate::detectors::DetectorConfig::crates); the step is dropped.
+    UnknownStepCrate,
+}
+
+/// Which built-in detector a [`StepWarning`] comes from.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DetectorKind {
+    /// `#[given(...)]`-style attributes.
+    Attribute,
+    /// `given!(...)`-style macros.
+    Macro,
+    /// `.given(...)`-style builder methods.
+    Builder,
+}
+
+/// A problem with a step definition itself, reported against its Rust source.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct StepWarning {
+    pub kind: WarningKind,
+    pub file: String,
+    /// 1-based line of the step attribute or macro.
+    pub line: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// Detector that found the definition; `None` for custom detectors and
+    /// [`WarningKind::InvalidPattern`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detector: Option<DetectorKind>,
+    pub message: String,
+}
+
+/// Steps and warnings extracted from a single file.
+#[derive(Debug, Default)]
+pub struct FileSteps {
+    pub steps: Vec<StepEntry>,
+    pub warnings: Vec<StepWarning>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -58,297 +357,326 @@ impl StepIndex {
     pub fn from_steps(mut steps: Vec<StepEntry>) -> Self {
         // Sort for stability
         steps.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        let stats = Stats::of(&steps);
+        StepIndex { steps, stats, ..StepIndex::default() }
+    }
+
+    /// Fills [`Stats::overlaps`]. Intersecting patterns costs far more than the other
+    /// stats, so neither [`StepIndex::from_steps`] nor the incremental indexer does it.
+    pub fn find_overlaps(&mut self) {
+        self.stats.overlaps = crate::ambiguity::overlaps(&self.steps);
+    }
+}
+
+impl Stats {
+    /// Counts and ambiguity of a set of steps, without [`Stats::overlaps`].
+    pub fn of<'a>(steps: impl IntoIterator<Item = &'a StepEntry>) -> Self {
         let mut stats = Stats::default();
-        stats.total = steps.len();
-        for s in &steps {
-            match s.kind {
-                StepKind::Given => stats.by_kind.given += 1,
-                StepKind::When => stats.by_kind.when += 1,
-                StepKind::Then => stats.by_kind.then += 1,
-            }
-        }
         // Ambiguity: same (kind, regex) appears more than once
         use std::collections::HashMap;
-        let mut map: HashMap<(StepKind, &str), usize> = HashMap::new();
-        for s in &steps {
-            *map.entry((s.kind, s.regex.as_str())).or_insert(0) += 1;
+        let mut map: HashMap<(StepKind, &str), Vec<&StepEntry>> = HashMap::new();
+        for s in steps {
+            stats.count(s, true);
+            map.entry((s.kind, s.regex.as_str())).or_default().push(s);
+        }
+        let groups = map.into_iter().filter(|(_, defs)| defs.len() > 1).map(|((kind, regex), defs)| Ambiguity {
+            kind,
+            regex: regex.to_string(),
+            steps: defs.into_iter().map(StepLocation::of).collect(),
+        });
+        stats.complete(groups)
+    }
+
+    /// Adds `step` to the counts (`total`, `by_kind`, `by_detector` and the step counts
+    /// of `by_file`), or takes it away again when `add` is false.
+    pub(crate) fn count(&mut self, step: &StepEntry, add: bool) {
+        let change = |n: &mut usize| if add { *n += 1 } else { *n -= 1 };
+        change(&mut self.total);
+        change(match step.kind {
+            StepKind::Given => &mut self.by_kind.given,
+            StepKind::When => &mut self.by_kind.when,
+            StepKind::Then => &mut self.by_kind.then,
+        });
+        change(match step.detector {
+            Some(DetectorKind::Attribute) => &mut self.by_detector.attribute,
+            Some(DetectorKind::Macro) => &mut self.by_detector.macros,
+            Some(DetectorKind::Builder) => &mut self.by_detector.builder,
+            None => &mut self.by_detector.other,
+        });
+        if add {
+            self.by_file.entry(step.file.clone()).or_default().steps += 1;
+        } else if let Some(file) = self.by_file.get_mut(&step.file) {
+            file.steps -= 1;
+            if file.steps == 0 {
+                self.by_file.remove(&step.file);
+            }
         }
-        stats.ambiguous = map.values().filter(|&&c| c > 1).count();
+    }
+
+    /// Counted stats with their ambiguities: `groups` are the `(kind, regex)` pairs
+    /// defined more than once, in any order.
+    pub(crate) fn complete(mut self, groups: impl IntoIterator<Item = Ambiguity>) -> Self {
+        for mut group in groups {
+            group.steps.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+            for loc in &group.steps {
+                self.by_file.entry(loc.file.clone()).or_default().ambiguous += 1;
+            }
+            self.ambiguities.push(group);
+        }
+        self.ambiguities.sort_by(|a, b| a.steps[0].sort_key().cmp(&b.steps[0].sort_key()).then(a.kind.cmp(&b.kind)));
+        self.ambiguous = self.ambiguities.len();
         // Timestamp for artifact freshness consumers (skip on wasm32)
         #[cfg(not(target_arch = "wasm32"))]
         {
-            stats.generated_at = Some(chrono::Utc::now().to_rfc3339());
+            self.generated_at = Some(chrono::Utc::now().to_rfc3339());
         }
-        StepIndex { steps, stats }
+        self
     }
 }
 
-// Pre-compiled detectors for performance and to avoid repeated unwraps
-static BUILDER_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\.(given|when|then)\s*(?:::<[^>]+>)?\s*\(")
-        .expect("valid builder chain regex")
-});
-static MACRO_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\b(given|when|then)!\s*\(")
-        .expect("valid step macro regex")
-});
-static ATTR_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new("(?s)#\\[\\s*(given|when|then)[^\\]]*\\]")
-        .expect("valid attribute block regex")
-});
-static FN_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?m)^\s*(?:pub(?:\([^)]+\))?\s+)?(?:async\s+)?fn\s+([A-Za-z_][A-Za-z0-9_]*)\s*\(")
-        .expect("valid function name regex")
-});
-
-pub fn extract_step_index_from_files(files: &[SourceFile]) -> StepIndex {
+/// Extracts the Step Index of a set of files, recognising steps with `detectors`
+/// (`&Detectors::default()` for the standard `given`/`when`/`then` forms).
+pub fn extract_step_index_from_files(files: &[SourceFile], detectors: &Detectors) -> StepIndex {
+    let consts = ConstTable::from_files(files);
     let mut out: Vec<StepEntry> = Vec::new();
+    let mut warnings: Vec<StepWarning> = Vec::new();
+    for found in map_files(files, |sf| extract_file(sf, &consts, detectors)) {
+        out.extend(found.steps);
+        warnings.extend(found.warnings);
+    }
+    warnings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    let mut index = StepIndex::from_steps(out);
+    index.warnings = warnings;
+    index.parameters = crate::parameters::from_files(files);
+    index
+}
 
-    // Use statics defined above to avoid recompiling regexes per invocation.
-
-    for sf in files {
-        let stripped = strip_comments_preserving_strings(&sf.text);
-        for (i, line) in stripped.lines().enumerate() {
-            let lineno = i + 1;
-            // Builder chains: .given/.when/.then(r"…"); collect all matches in a line
-            for cap in BUILDER_RE.captures_iter(line) {
-                let kind = kind_from_lower(cap.get(1).map(|m| m.as_str()).unwrap_or(""));
-                if let Some(m0) = cap.get(0) {
-                    let after = &line[m0.end()..];
-                    if let Some(regex_text) = extract_first_string_literal(after) {
-                        out.push(StepEntry {
-                            kind,
-                            regex: regex_text,
-                            file: sf.path.clone(),
-                            line: lineno,
-                            function: None,
-                            captures: None,
-                            tags: None,
-                            notes: None,
-                        });
-                    }
-                }
-            }
+/// Applies `f` to every file, on the rayon thread pool with the `parallel` feature;
+/// results are in file order either way.
+pub(crate) fn map_files<R, F>(files: &[SourceFile], f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(&SourceFile) -> R + Sync + Send,
+{
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    {
+        use rayon::prelude::*;
+        files.par_iter().map(f).collect()
+    }
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    {
+        files.iter().map(f).collect()
+    }
+}
 
-            // Macros: given!/when!/then!(r"…", ...); collect all matches in a line
-            for cap in MACRO_RE.captures_iter(line) {
-                let kind = kind_from_lower(cap.get(1).map(|m| m.as_str()).unwrap_or(""));
-                if let Some(m0) = cap.get(0) {
-                    let after = &line[m0.end()..];
-                    if let Some(regex_text) = extract_first_string_literal(after) {
-                        out.push(StepEntry {
-                            kind,
-                            regex: regex_text,
-                            file: sf.path.clone(),
-                            line: lineno,
-                            function: None,
-                            captures: None,
-                            tags: None,
-                            notes: None,
-                        });
-                    }
-                }
-            }
+/// Extracts the steps of a single file. With the `syn` feature the AST extractor is
+/// tried first; files it cannot parse fall back to the regex scanner. Patterns that do
+/// not compile are flagged, see [`StepEntry::invalid`].
+pub(crate) fn extract_file(sf: &SourceFile, consts: &ConstTable, detectors: &Detectors) -> FileSteps {
+    #[cfg(feature = "syn")]
+    let found = crate::syn_extract::extract_file(sf, consts, detectors).map(|mut found| {
+        if detectors.has_custom() {
+            detectors.scan_custom(&ScannedFile::new(sf, consts, &detectors.crates), &mut found);
         }
+        found
+    });
+    #[cfg(feature = "syn")]
+    let mut found = found.unwrap_or_else(|| scan_file(sf, consts, detectors));
+    #[cfg(not(feature = "syn"))]
+    let mut found = scan_file(sf, consts, detectors);
+    flag_invalid_patterns(&mut found);
+    found
+}
 
-        // Attribute macros: scan across the stripped file text for multi-line #[given/when/then(...)] blocks
-        for cap in ATTR_BLOCK_RE.captures_iter(&stripped) {
-            let kind = kind_from_lower(cap.get(1).map(|m| m.as_str()).unwrap_or(""));
-            if let Some(m0) = cap.get(0) {
-                let matched = &stripped[m0.start()..m0.end()];
-                if let (Some(lb), Some(rb)) = (matched.find('['), matched.rfind(']')) {
-                    let inside = &matched[lb + 1..rb];
-                    if let Some(regex_text) = extract_first_string_literal(inside) {
-                        // line number: count newlines up to match start
-                        let lineno = stripped[..m0.start()].bytes().filter(|&b| b == b'\n').count() + 1;
-                        // best-effort function name capture from the next few lines
-                        let suffix = &stripped[m0.end()..];
-                        let fn_scope: String = suffix.lines().take(4).collect::<Vec<_>>().join("\n");
-                        let function = FN_RE
-                            .captures(&fn_scope)
-                            .and_then(|c| c.get(1).map(|m| m.as_str().to_string()));
-                        out.push(StepEntry {
-                            kind,
-                            regex: regex_text,
-                            file: sf.path.clone(),
-                            line: lineno,
-                            function,
-                            captures: None,
-                            tags: None,
-                            notes: None,
-                        });
-                    }
-                }
-            }
-        }
+/// Records why each pattern that does not compile is invalid, with a warning.
+fn flag_invalid_patterns(found: &mut FileSteps) {
+    for step in &mut found.steps {
+        let Some(reason) = step.pattern_error() else {
+            continue;
+        };
+        found.warnings.push(StepWarning {
+            kind: WarningKind::InvalidPattern,
+            file: step.file.clone(),
+            line: step.line,
+            function: step.function.clone(),
+            detector: None,
+            message: format!("{reason}; the step never matches"),
+        });
+        step.invalid = Some(reason);
     }
+}
 
-    StepIndex::from_steps(out)
+/// Regex-based scanner over the comment-stripped file text.
+pub(crate) fn scan_file(sf: &SourceFile, consts: &ConstTable, detectors: &Detectors) -> FileSteps {
+    detectors.scan(&ScannedFile::new(sf, consts, &detectors.crates))
 }
 
-fn kind_from_lower(s: &str) -> StepKind {
-    match s.to_ascii_lowercase().as_str() {
-        "given" => StepKind::Given,
-        "when" => StepKind::When,
-        _ => StepKind::Then,
+/// A regex step at `src/steps.rs:1` with nothing else known, for tests to complete with
+/// struct update syntax.
+#[cfg(test)]
+pub(crate) fn entry(kind: StepKind, regex: &str) -> StepEntry {
+    StepEntry {
+        kind,
+        regex: regex.into(),
+        pattern_kind: PatternKind::Regex,
+        file: "src/steps.rs".into(),
+        line: 1,
+        function: None,
+        module: None,
+        captures: None,
+        tags: None,
+        notes: None,
+        extractor: None,
+        detector: None,
+        unresolved: None,
+        invalid: None,
+        span: None,
+        pattern_span: None,
+        function_span: None,
+        expects_table: None,
+        expects_docstring: None,
     }
 }
 
 /// Extracts the first Rust string literal (normal or raw) from `s`
 /// and returns its inner content without delimiters.
+#[cfg(test)]
 fn extract_first_string_literal(s: &str) -> Option<String> {
-    let bytes = s.as_bytes();
-    let len = bytes.len();
-    let mut i = 0;
-    while i < len {
-        let b = bytes[i];
-        if b == b'r' {
-            // raw string: r#*" ... "#*
-            let mut j = i + 1;
-            let mut hashes = 0usize;
-            while j < len && bytes[j] == b'#' {
-                hashes += 1;
-                j += 1;
-            }
-            if j < len && bytes[j] == b'"' {
-                j += 1; // skip opening quote
-                let start = j;
-                // find closing match: '"' followed by `hashes` '#'
-                while j < len {
-                    if bytes[j] == b'"' {
-                        let mut ok = true;
-                        for h in 0..hashes {
-                            if j + 1 + h >= len || bytes[j + 1 + h] != b'#' {
-                                ok = false;
-                                break;
-                            }
-                        }
-                        if ok {
-                            let end = j;
-                            return Some(String::from_utf8_lossy(&bytes[start..end]).into_owned());
-                        }
-                    }
-                    j += 1;
-                }
-                return None; // unterminated
-            }
-        }
-        if b == b'"' {
-            // normal string
-            let mut j = i + 1;
-            let mut out = String::new();
-            while j < len {
-                let c = bytes[j];
-                if c == b'\\' {
-                    if j + 1 >= len {
-                        return None;
-                    }
-                    let e = bytes[j + 1];
-                    match e {
-                        b'"' => out.push('"'),
-                        b'\\' => out.push('\\'),
-                        b'n' => out.push('\n'),
-                        b'r' => out.push('\r'),
-                        b't' => out.push('\t'),
-                        _ => {
-                            // keep unknown escape sequences as-is
-                            out.push('\\');
-                            out.push(e as char);
-                        }
-                    }
-                    j += 2;
-                    continue;
-                } else if c == b'"' {
-                    return Some(out);
-                } else {
-                    out.push(c as char);
-                    j += 1;
-                }
-            }
-            return None; // unterminated
+    find_first_string_literal(s).map(|(_, _, lit)| lit)
+}
+
+/// Like [`extract_first_string_literal`], also returning the literal's start and end offsets.
+#[cfg(test)]
+fn find_first_string_literal(s: &str) -> Option<(usize, usize, String)> {
+    for t in tokens(s) {
+        match string_literal_at(s, t.start) {
+            LiteralAt::Found(lit, end) => return Some((t.start, end, lit)),
+            LiteralAt::Unterminated => return None,
+            LiteralAt::None => {}
         }
-        i += 1;
     }
     None
 }
 
-/// Remove line (// ...) and block (/* ... */) comments while preserving string literals and newlines.
-fn strip_comments_preserving_strings(s: &str) -> String {
-    let bytes = s.as_bytes();
-    let mut out = String::with_capacity(s.len());
-    let mut i = 0;
-    let len = bytes.len();
-    let mut in_block = false;
-    let mut in_normal = false;
-    let mut in_raw = false;
-    let mut raw_hashes = 0usize;
-    while i < len {
-        let b = bytes[i];
-        if in_block {
-            if b == b'*' && i + 1 < len && bytes[i + 1] == b'/' {
-                in_block = false;
-                out.push(' ');
-                out.push(' ');
-                i += 2;
-            } else {
-                // replace with spaces, preserve newlines
-                if b == b'\n' { out.push('\n'); } else { out.push(' '); }
-                i += 1;
-            }
+/// Outcome of reading a string literal at a given byte offset.
+pub(crate) enum LiteralAt {
+    /// No string literal starts at this offset.
+    None,
+    /// Unescaped content and the offset just past the closing delimiter.
+    Found(String, usize),
+    /// A literal starts here but is never closed.
+    Unterminated,
+}
+
+/// Reads the Rust string literal (normal or raw) starting at byte offset `i` of `s`, if any.
+/// Byte and C strings are not string literals; they are `Unterminated` when unclosed, as
+/// everything after them is then part of the literal.
+pub(crate) fn string_literal_at(s: &str, i: usize) -> LiteralAt {
+    if i >= s.len() || !s.is_char_boundary(i) {
+        return LiteralAt::None;
+    }
+    let t = token_at(s, i);
+    match t.kind {
+        TokenKind::Str | TokenKind::OtherStr if !t.terminated => LiteralAt::Unterminated,
+        TokenKind::Str => LiteralAt::Found(string_literal_value(&s[t.start..t.end]), t.end),
+        _ => LiteralAt::None,
+    }
+}
+
+/// Content of a terminated string literal token: raw strings verbatim, normal strings
+/// with their escapes decoded.
+fn string_literal_value(literal: &str) -> String {
+    if let Some(raw) = literal.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        return raw[hashes + 1..raw.len() - hashes - 1].to_string();
+    }
+    let content = &literal[1..literal.len() - 1];
+    let mut out = String::new();
+    let mut chars = content.char_indices();
+    while let Some((j, c)) = chars.next() {
+        if c != '\\' {
+            out.push(c);
             continue;
         }
-        if in_normal {
-            out.push(b as char);
-            if b == b'\\' {
-                if i + 1 < len { out.push(bytes[i + 1] as char); i += 2; } else { i += 1; }
-                continue;
-            }
-            if b == b'"' { in_normal = false; }
-            i += 1;
-            continue;
+        let rest = &content[j + 1..];
+        let (text, used) = unescape(rest);
+        match text {
+            Some(text) => out.push_str(&text),
+            // keep unknown escape sequences as-is
+            None => out.push_str(&content[j..j + 1 + used]),
         }
-        if in_raw {
-            out.push(b as char);
-            if b == b'"' {
-                // check for closing raw string by matching hashes
-                let mut ok = true;
-                for h in 0..raw_hashes {
-                    if i + 1 + h >= len || bytes[i + 1 + h] != b'#' { ok = false; break; }
-                }
-                if ok { in_raw = false; }
-            }
-            i += 1;
-            continue;
+        // `used` stays on char boundaries: escapes end in ASCII or whitespace
+        for _ in rest[..used].chars() {
+            chars.next();
         }
-        // Not in any special mode
-        if b == b'/' && i + 1 < len && bytes[i + 1] == b'/' {
-            // line comment: replace rest of line with spaces
-            while i < len && bytes[i] != b'\n' { out.push(' '); i += 1; }
-            continue;
+    }
+    out
+}
+
+/// Decodes the escape sequence following a backslash: its text (`None` for unknown
+/// escapes) and its length in bytes, including a line continuation's skipped whitespace.
+fn unescape(rest: &str) -> (Option<String>, usize) {
+    let Some(e) = rest.chars().next() else {
+        return (None, 0);
+    };
+    let simple = match e {
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '\\' => Some('\\'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '0' => Some('\0'),
+        _ => None,
+    };
+    if let Some(c) = simple {
+        return (Some(c.to_string()), 1);
+    }
+    match e {
+        '\n' | '\r' if rest.starts_with('\n') || rest.starts_with("\r\n") => {
+            let skipped = rest.len() - rest.trim_start().len();
+            (Some(String::new()), skipped)
         }
-        if b == b'/' && i + 1 < len && bytes[i + 1] == b'*' {
-            in_block = true;
-            out.push(' ');
-            out.push(' ');
-            i += 2;
-            continue;
+        'x' => {
+            let code = rest.get(1..3).and_then(|hex| u8::from_str_radix(hex, 16).ok()).filter(|b| b.is_ascii());
+            match code {
+                Some(b) => (Some(char::from(b).to_string()), 3),
+                None => (None, 1),
+            }
         }
-        if b == b'r' {
-            // possible raw string
-            let mut j = i + 1; let mut hashes = 0usize;
-            while j < len && bytes[j] == b'#' { hashes += 1; j += 1; }
-            if j < len && bytes[j] == b'"' {
-                in_raw = true; raw_hashes = hashes; out.push('r');
-                for _ in 0..hashes { out.push('#'); }
-                out.push('"');
-                i = j + 1; continue;
+        'u' => {
+            let close = rest.strip_prefix("u{").and_then(|r| r.find('}'));
+            let c = close.and_then(|close| {
+                let hex = rest[2..2 + close].replace('_', "");
+                u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+            });
+            match (c, close) {
+                (Some(c), Some(close)) => (Some(c.to_string()), 2 + close + 1),
+                _ => (None, 1),
             }
         }
-        if b == b'"' {
-            in_normal = true; out.push('"'); i += 1; continue;
+        _ => (None, e.len_utf8()),
+    }
+}
+
+/// Remove line (// ...) and block (/* ... */, nested) comments while preserving string
+/// literals and newlines. Every comment byte becomes a space, so byte offsets into the
+/// result are offsets into `s`.
+pub(crate) fn strip_comments_preserving_strings(s: &str) -> String {
+    strip_comment_tokens(s, tokens(s))
+}
+
+/// Like [`strip_comments_preserving_strings`], over the already lexed `toks` of `s`.
+pub(crate) fn strip_comment_tokens(s: &str, toks: impl IntoIterator<Item = Token>) -> String {
+    let mut out = String::with_capacity(s.len());
+    for t in toks {
+        let text = &s[t.start..t.end];
+        if t.kind == TokenKind::Comment {
+            // replace with spaces, preserve newlines
+            out.extend(text.bytes().map(|b| if b == b'\n' { '\n' } else { ' ' }));
+        } else {
+            out.push_str(text);
         }
-        out.push(b as char);
-        i += 1;
     }
     out
 }
@@ -369,6 +697,26 @@ mod tests {
         assert_eq!(extract_first_string_literal("\"hello\\nworld\""), Some("hello\nworld".to_string()));
     }
 
+    #[test]
+    fn test_extract_unicode_and_escapes() {
+        assert_eq!(extract_first_string_literal("\"^ich habe (\\d+) Gurken für Jürgen$\""), Some("^ich habe (\\d+) Gurken für Jürgen$".to_string()));
+        assert_eq!(extract_first_string_literal("r\"^キュウリが(\\d+)本$\""), Some("^キュウリが(\\d+)本$".to_string()));
+        assert_eq!(extract_first_string_literal("\"caf\\u{e9} \\u{1F952} \\x41\\0\\'\""), Some("café 🥒 A\0'".to_string()));
+        assert_eq!(extract_first_string_literal("\"one \\\n    two\""), Some("one two".to_string()));
+        assert_eq!(extract_first_string_literal("\"bad \\u{110000} \\xFF\""), Some("bad \\u{110000} \\xFF".to_string()));
+        assert_eq!(find_first_string_literal("/*ü*/ \"é\" x").map(|(s, e, _)| (s, e)), Some((7, 11)));
+    }
+
+    #[test]
+    fn test_strip_preserves_bytes() {
+        let text = "// Gurken für Jürgen\ngiven!(\"^grüß dich // 🥒$\", f); /* ü */ when!(\"ü\");";
+        let stripped = strip_comments_preserving_strings(text);
+        assert_eq!(stripped.len(), text.len());
+        assert_eq!(stripped.find("given!"), text.find("given!"));
+        assert!(stripped.contains("\"^grüß dich // 🥒$\""));
+        assert!(!stripped.contains("Jürgen") && stripped.contains("when!(\"ü\")"));
+    }
+
     #[test]
     fn test_extract_index_builder_macro_attr() {
         let files = vec![SourceFile {
@@ -383,11 +731,23 @@ mod tests {
                 fn ok() {}
             "#.into(),
         }];
-        let idx = extract_step_index_from_files(&files);
+        let idx = extract_step_index_from_files(&files, &Detectors::default());
         assert_eq!(idx.stats.total, 4);
         assert_eq!(idx.stats.by_kind.given, 2);
         assert_eq!(idx.stats.by_kind.when, 1);
         assert_eq!(idx.stats.by_kind.then, 1);
         assert!(idx.steps.iter().any(|s| s.regex.contains("^I have")));
     }
+
+    #[test]
+    fn test_ambiguity_order() {
+        let step = |kind, function: &str| StepEntry { function: Some(function.into()), ..entry(kind, "^a$") };
+        let mut steps = vec![step(StepKind::When, "y"), step(StepKind::Given, "y"), step(StepKind::When, "x"), step(StepKind::Given, "x")];
+        let stats = Stats::of(&steps);
+        let groups: Vec<(StepKind, Vec<&str>)> =
+            stats.ambiguities.iter().map(|g| (g.kind, g.steps.iter().filter_map(|s| s.function.as_deref()).collect())).collect();
+        assert_eq!(groups, vec![(StepKind::Given, vec!["x", "y"]), (StepKind::When, vec!["x", "y"])]);
+        steps.reverse();
+        assert_eq!(Stats::of(&steps).ambiguities, stats.ambiguities);
+    }
 }
@@ -0,0 +1,637 @@
+//! AST-based step extraction (`syn` cargo feature).
+//!
+//! Parses a [`SourceFile`] with `syn` and collects the attributed functions, macro calls
+//! and builder calls whose names the [`Detectors`] recognise (`#[given(...)]`, `given!`,
+//! `.given(...)` and any configured ones). Custom [`StepDetector`](crate::detectors::StepDetector)s
+//! are not run here.
+//! Returns `None` when the file does not parse so callers can fall back to the scanner.
+//! Each file's proc-macro2 span data is freed once the file is done, which invalidates
+//! any `proc_macro2::Span` the calling thread still holds.
+
+use std::ops::Range;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::ext::IdentExt;
+use syn::{
+    Attribute, Block, Expr, ExprMethodCall, FnArg, ImplItemFn, ItemFn, ItemMod, Lit, Macro, MacroDelimiter, Pat, Signature, Token,
+    TraitItemFn,
+};
+
+use crate::captures::{captures_for, count_mismatch, step_arg_usage, step_params, FnParam};
+use crate::consts::{is_const_path, ConstTable};
+use crate::detectors::{kind_from_arg, Detectors, Dropped};
+use crate::docs::doc_comment;
+use crate::imports::Imports;
+use crate::lexer::tokens;
+use crate::modules::file_module;
+use crate::step_index::{DetectorKind, Extractor, FileSteps, LineIndex, PatternKind, SourceFile, StepEntry, StepKind};
+
+pub(crate) fn extract_file(sf: &SourceFile, consts: &ConstTable, detectors: &Detectors) -> Option<FileSteps> {
+    let found = visit_file(sf, consts, detectors);
+    // with `span-locations`, proc-macro2 keeps the text of every file it tokenizes in a
+    // thread-local source map with 32-bit offsets; no span outlives `visit_file`
+    proc_macro2::extra::invalidate_current_thread_spans();
+    found
+}
+
+fn visit_file(sf: &SourceFile, consts: &ConstTable, detectors: &Detectors) -> Option<FileSteps> {
+    let file = syn::parse_file(&sf.text).ok()?;
+    let mut visitor = StepVisitor {
+        file: &sf.path,
+        text: &sf.text,
+        consts,
+        detectors,
+        imports: Imports::scan(&sf.text, tokens(&sf.text)),
+        module: file_module(&sf.path),
+        lines: LineIndex::new(&sf.text),
+        base: parsed_offset(&sf.text),
+        out: FileSteps::default(),
+    };
+    visitor.visit_file(&file);
+    Some(visitor.out)
+}
+
+/// Offset of the text `syn::parse_file` actually parses: it drops a leading byte order
+/// mark and shebang line, and spans are relative to what remains.
+fn parsed_offset(text: &str) -> usize {
+    let rest = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut offset = text.len() - rest.len();
+    if rest.starts_with("#!") && !rest[2..].trim_start().starts_with('[') {
+        offset += rest.find('\n').unwrap_or(rest.len());
+    }
+    offset
+}
+
+struct StepVisitor<'a> {
+    file: &'a str,
+    text: &'a str,
+    consts: &'a ConstTable,
+    detectors: &'a Detectors,
+    imports: Imports,
+    /// Path of the module being visited: the file's module plus enclosing inline `mod`s.
+    module: String,
+    lines: LineIndex<'a>,
+    base: usize,
+    out: FileSteps,
+}
+
+/// A step pattern argument: resolved text, or its source text and the reason it is
+/// unresolved, plus the argument's byte range in the file.
+type ArgPattern = (String, Option<String>, Range<usize>);
+
+/// The function of an attributed step: name, byte range of the name and of its `fn`
+/// keyword, step parameters and whether it reads `step.table` / `step.docstring`.
+struct StepFn {
+    name: String,
+    name_range: Range<usize>,
+    fn_start: usize,
+    params: Vec<FnParam>,
+    usage: (Option<bool>, Option<bool>),
+}
+
+impl StepVisitor<'_> {
+    /// The step name an attribute or macro path refers to: its last segment, following
+    /// the file's imports when it is a bare name. A qualified path must start with a step
+    /// root (cucumber, a configured crate, the crate's own modules or an import of one).
+    fn step_name(&self, path: &syn::Path) -> Option<String> {
+        let last = path.segments.last()?.ident.unraw().to_string();
+        if path.leading_colon.is_some() || path.segments.len() > 1 {
+            let root = path.segments.first()?.ident.unraw().to_string();
+            return self.imports.step_root(&root, &self.detectors.crates).then_some(last);
+        }
+        self.imports.resolve(&last, &self.detectors.crates).map(str::to_string)
+    }
+
+    /// Why a step name imported from a crate not known to define steps is dropped: `kind_of`
+    /// tells whether `path`, a bare name, is one of the step names.
+    fn foreign_import(&self, path: &syn::Path, kind_of: impl Fn(&str) -> bool) -> Option<Dropped> {
+        let name = path.get_ident()?.unraw().to_string();
+        if !kind_of(&name) {
+            return None;
+        }
+        self.imports.foreign_path(&name, &self.detectors.crates).map(Dropped::ForeignImport)
+    }
+
+    /// Byte range of a span in the file text.
+    fn range(&self, span: Span) -> Range<usize> {
+        let r = span.byte_range();
+        r.start + self.base..r.end + self.base
+    }
+
+    fn push(
+        &mut self,
+        kind: StepKind,
+        (regex, unresolved, pattern): ArgPattern,
+        pattern_kind: PatternKind,
+        def: Range<usize>,
+        function: Option<StepFn>,
+        detector: DetectorKind,
+    ) {
+        let captures = match (&unresolved, &function) {
+            (Some(_), _) => None,
+            (None, Some(f)) => captures_for(&regex, pattern_kind, Some(&f.params)),
+            (None, None) => captures_for(&regex, pattern_kind, None),
+        };
+        let span = self.lines.span(def.start, def.end);
+        let item_line = function.as_ref().map_or(span.start_line, |f| self.lines.span(f.fn_start, f.fn_start).start_line);
+        let entry = StepEntry {
+            kind,
+            regex,
+            pattern_kind,
+            file: self.file.to_string(),
+            line: span.start_line,
+            function: function.as_ref().map(|f| f.name.clone()),
+            module: Some(self.module.clone()),
+            captures,
+            tags: None,
+            notes: doc_comment(self.text, span.start_line, item_line),
+            extractor: Some(Extractor::Syn),
+            detector: Some(detector),
+            unresolved,
+            invalid: None,
+            span: Some(span),
+            pattern_span: Some(self.lines.span(pattern.start, pattern.end)),
+            function_span: function.as_ref().map(|f| self.lines.span(f.name_range.start, f.name_range.end)),
+            expects_table: function.as_ref().and_then(|f| f.usage.0),
+            expects_docstring: function.as_ref().and_then(|f| f.usage.1),
+        };
+        let params = function.map(|f| f.params);
+        if let Some(params) = &params {
+            self.out.warnings.extend(count_mismatch(&entry, params));
+        }
+        self.out.steps.push(entry);
+    }
+
+    /// Pattern of a string literal, const path or `concat!` expression; `None` for anything else.
+    fn expr_pattern(&self, e: &Expr) -> Option<ArgPattern> {
+        let resolved = match e {
+            Expr::Lit(syn::ExprLit { lit: Lit::Str(s), .. }) => Ok(s.value()),
+            Expr::Path(p) if p.qself.is_none() => {
+                let name = p.path.segments.last()?.ident.to_string();
+                if !is_const_path(&name) {
+                    return None;
+                }
+                self.consts.resolve_name(self.file, &name)
+            }
+            Expr::Macro(m) if m.mac.path.segments.last().is_some_and(|s| s.ident == "concat") => self.concat(&m.mac),
+            _ => return None,
+        };
+        let range = self.range(e.span());
+        Some(match resolved {
+            Ok(text) => (text, None, range),
+            Err(reason) => (e.span().source_text().unwrap_or_default(), Some(reason), range),
+        })
+    }
+
+    fn concat(&self, mac: &Macro) -> Result<String, String> {
+        let parts = mac
+            .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)
+            .map_err(|e| format!("cannot evaluate `concat!`: {e}"))?;
+        let mut out = String::new();
+        for part in &parts {
+            match self.expr_pattern(part) {
+                Some((text, None, _)) => out.push_str(&text),
+                Some((_, Some(reason), _)) => return Err(reason),
+                None => return Err(format!("cannot evaluate `{}`", part.span().source_text().unwrap_or_default())),
+            }
+        }
+        Ok(out)
+    }
+
+    /// First attribute argument that is a string literal, const or `concat!`, classified
+    /// like the scanner does: bare value, `expr = ...` or any other `name = ...`. Without a
+    /// `kind`, the first argument names the step kind and the pattern follows it.
+    fn attr_pattern(&self, kind: Option<StepKind>, tokens: TokenStream) -> Result<(StepKind, PatternKind, ArgPattern), Dropped> {
+        let Ok(args) = syn::parse::Parser::parse2(Punctuated::<Expr, Token![,]>::parse_terminated, tokens.clone()) else {
+            let kind = kind.ok_or(Dropped::NoStepKind)?;
+            return attr_literal(tokens)
+                .map(|(pattern_kind, (text, span))| (kind, pattern_kind, (text, None, self.range(span))))
+                .ok_or(Dropped::NoPattern);
+        };
+        let (kind, skip) = step_kind(kind, args.first()).ok_or(Dropped::NoStepKind)?;
+        args.iter().skip(skip).find_map(|arg| {
+            let (key, value) = match arg {
+                Expr::Assign(a) => match a.left.as_ref() {
+                    Expr::Path(p) => (p.path.get_ident().map(|i| i.to_string()), a.right.as_ref()),
+                    _ => return None,
+                },
+                _ => (None, arg),
+            };
+            let pattern_kind = match key.as_deref() {
+                None => PatternKind::Literal,
+                Some("expr") => PatternKind::Expression,
+                Some(_) => PatternKind::Regex,
+            };
+            self.expr_pattern(value).map(|p| (kind, pattern_kind, p))
+        })
+        .ok_or(Dropped::NoPattern)
+    }
+
+    /// Records a dropped step definition written `what` starting at byte offset `at`.
+    fn dropped(&mut self, reason: Dropped, detector: DetectorKind, what: &str, at: usize, function: Option<String>) {
+        let line = self.lines.span(at, at).start_line;
+        self.out.warnings.push(reason.warning(self.file, line, function, detector, what));
+    }
+
+    /// `item_end` is the end of the function item in the file.
+    fn step_attrs(&mut self, attrs: &[Attribute], sig: &Signature, body: Option<&Block>, item_end: usize) {
+        for attr in attrs {
+            let start = self.range(attr.pound_token.span).start;
+            let Some(kind) = self.step_name(attr.path()).and_then(|name| self.detectors.attributes.kind_of(&name)) else {
+                if let Some(reason) = self.foreign_import(attr.path(), |name| self.detectors.attributes.kind_of(name).is_some()) {
+                    let what = format!("`#[{}]`", attr.path().segments[0].ident.unraw());
+                    self.dropped(reason, DetectorKind::Attribute, &what, start, Some(sig.ident.to_string()));
+                }
+                continue;
+            };
+            let found = match &attr.meta {
+                syn::Meta::List(list) => self.attr_pattern(kind, list.tokens.clone()),
+                _ => Err(Dropped::NoArguments),
+            };
+            let (kind, pattern_kind, pattern) = match found {
+                Ok(found) => found,
+                Err(reason) => {
+                    let name = attr.path().segments.last().map(|s| s.ident.unraw().to_string()).unwrap_or_default();
+                    self.dropped(reason, DetectorKind::Attribute, &format!("`#[{name}]`"), start, Some(sig.ident.to_string()));
+                    continue;
+                }
+            };
+            let all = fn_params(sig);
+            let body = body.and_then(|b| b.span().source_text()).unwrap_or_default();
+            let function = StepFn {
+                name: sig.ident.to_string(),
+                name_range: self.range(sig.ident.span()),
+                fn_start: self.range(sig.fn_token.span).start,
+                usage: step_arg_usage(&all, &body),
+                params: step_params(all),
+            };
+            self.push(kind, pattern, pattern_kind, start..item_end, Some(function), DetectorKind::Attribute);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for StepVisitor<'_> {
+    fn visit_item_mod(&mut self, m: &'ast ItemMod) {
+        let outer = self.module.len();
+        self.module.push_str("::");
+        self.module.push_str(&m.ident.unraw().to_string());
+        visit::visit_item_mod(self, m);
+        self.module.truncate(outer);
+    }
+
+    fn visit_item_fn(&mut self, f: &'ast ItemFn) {
+        self.step_attrs(&f.attrs, &f.sig, Some(&f.block), self.range(f.span()).end);
+        visit::visit_item_fn(self, f);
+    }
+
+    fn visit_impl_item_fn(&mut self, f: &'ast ImplItemFn) {
+        self.step_attrs(&f.attrs, &f.sig, Some(&f.block), self.range(f.span()).end);
+        visit::visit_impl_item_fn(self, f);
+    }
+
+    fn visit_trait_item_fn(&mut self, f: &'ast TraitItemFn) {
+        self.step_attrs(&f.attrs, &f.sig, f.default.as_ref(), self.range(f.span()).end);
+        visit::visit_trait_item_fn(self, f);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let Some(last) = mac.path.segments.last() else {
+            return;
+        };
+        if let Some(kind) = self.step_name(&mac.path).and_then(|name| self.detectors.macros.kind_of(&name)) {
+            let step = match mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+                Ok(args) => step_kind(kind, args.first()).ok_or(Dropped::NoStepKind).and_then(|(kind, skip)| {
+                    args.iter().nth(skip).and_then(|arg| self.expr_pattern(arg)).map(|p| (kind, p)).ok_or(Dropped::NoPattern)
+                }),
+                Err(_) => match (kind, mac.tokens.clone().into_iter().next()) {
+                    (Some(kind), Some(TokenTree::Literal(lit))) => match Lit::new(lit) {
+                        Lit::Str(s) => Ok((kind, (s.value(), None, self.range(s.span())))),
+                        _ => Err(Dropped::NoPattern),
+                    },
+                    (Some(_), _) => Err(Dropped::NoPattern),
+                    (None, _) => Err(Dropped::NoStepKind),
+                },
+            };
+            let start = self.range(last.ident.span()).start;
+            match step {
+                Ok((kind, pattern)) => {
+                    let close = match &mac.delimiter {
+                        MacroDelimiter::Paren(d) => d.span.close(),
+                        MacroDelimiter::Brace(d) => d.span.close(),
+                        MacroDelimiter::Bracket(d) => d.span.close(),
+                    };
+                    let def = start..self.range(close).end;
+                    self.push(kind, pattern, PatternKind::Regex, def, None, DetectorKind::Macro);
+                }
+                Err(reason) => self.dropped(reason, DetectorKind::Macro, &format!("`{}!`", last.ident.unraw()), start, None),
+            }
+            return;
+        }
+        if let Some(reason) = self.foreign_import(&mac.path, |name| self.detectors.macros.kind_of(name).is_some()) {
+            let start = self.range(last.ident.span()).start;
+            return self.dropped(reason, DetectorKind::Macro, &format!("`{}!`", last.ident.unraw()), start, None);
+        }
+        if last.ident == "macro_rules" {
+            return;
+        }
+        // Expression-list macros (`vec!`, `assert!`, `tokio::join!`, ...) may wrap builder calls.
+        if let Ok(exprs) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for e in &exprs {
+                self.visit_expr(e);
+            }
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        if let Some((kind, skip)) = self.detectors.builders.kind_of(&call.method.to_string()).and_then(|k| step_kind(k, call.args.first())) {
+            if let Some(pattern) = call.args.iter().nth(skip).and_then(|arg| self.expr_pattern(arg)) {
+                let def = self.range(call.method.span()).start..self.range(call.paren_token.span.close()).end;
+                self.push(kind, pattern, PatternKind::Regex, def, None, DetectorKind::Builder);
+            }
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+fn fn_params(sig: &Signature) -> Vec<FnParam> {
+    sig.inputs
+        .iter()
+        .map(|arg| match arg {
+            // typed by its own text (`&mut self`) unless written `self: Type`, as the scanner does
+            FnArg::Receiver(r) => FnParam {
+                name: "self".into(),
+                ty: match r.colon_token {
+                    Some(_) => r.ty.span().source_text(),
+                    None => r.span().source_text(),
+                }
+                .unwrap_or_default(),
+            },
+            FnArg::Typed(t) => FnParam {
+                name: match t.pat.as_ref() {
+                    Pat::Ident(p) => p.ident.to_string(),
+                    other => other.span().source_text().unwrap_or_default(),
+                },
+                ty: t.ty.span().source_text().unwrap_or_default(),
+            },
+        })
+        .collect()
+}
+
+/// Step kind of a detected name and how many leading arguments to skip for the pattern:
+/// a name without a kind takes it from its first argument (`step!(Given, "...")`).
+fn step_kind(kind: Option<StepKind>, first: Option<&Expr>) -> Option<(StepKind, usize)> {
+    match (kind, first) {
+        (Some(kind), _) => Some((kind, 0)),
+        (None, Some(Expr::Path(p))) => kind_from_arg(&p.path.segments.last()?.ident.to_string()).map(|kind| (kind, 1)),
+        _ => None,
+    }
+}
+
+/// First top-level string literal of an attribute whose arguments are not expressions.
+fn attr_literal(tokens: TokenStream) -> Option<(PatternKind, (String, Span))> {
+    let tts: Vec<TokenTree> = tokens.into_iter().collect();
+    for (i, tt) in tts.iter().enumerate() {
+        let TokenTree::Literal(lit) = tt else {
+            continue;
+        };
+        let Lit::Str(s) = Lit::new(lit.clone()) else {
+            continue;
+        };
+        let after_eq = i >= 1 && matches!(&tts[i - 1], TokenTree::Punct(p) if p.as_char() == '=');
+        let pattern_kind = if !after_eq {
+            PatternKind::Literal
+        } else if i >= 2 && matches!(&tts[i - 2], TokenTree::Ident(id) if id == "expr") {
+            PatternKind::Expression
+        } else {
+            PatternKind::Regex
+        };
+        return Some((pattern_kind, (s.value(), lit.span())));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::DetectorConfig;
+    use crate::step_index::{scan_file, Span};
+
+    fn file(text: &str) -> SourceFile {
+        SourceFile { path: "src/steps.rs".into(), text: text.into() }
+    }
+
+    fn extract(sf: &SourceFile) -> Option<Vec<StepEntry>> {
+        extract_file(sf, &ConstTable::from_files(std::slice::from_ref(sf)), &Detectors::default()).map(|found| found.steps)
+    }
+
+    #[test]
+    fn test_spans_are_released() {
+        let sf = file("#[given(\"^a$\")]\nfn a(w: &mut World) {}\n");
+        let broken = file("#[given(\"^b$\")]\nfn b(w: &mut World) {");
+        for _ in 0..100 {
+            assert_eq!(extract(&sf).map(|steps| steps.len()), Some(1));
+            assert_eq!(extract(&broken), None);
+        }
+        // the source map starts over after each file instead of growing to 100 copies
+        let fresh: TokenStream = "x".parse().unwrap();
+        let span = format!("{:?}", fresh.into_iter().next().unwrap().span());
+        assert_eq!(span, "bytes(1..2)");
+    }
+
+    type Summary = (StepKind, String, PatternKind, usize, Option<String>, Option<String>, Option<Vec<crate::step_index::Capture>>, [Option<Span>; 3], [Option<bool>; 2], Option<String>);
+
+    fn summary(steps: &[StepEntry]) -> Vec<Summary> {
+        let mut v: Vec<_> = steps
+            .iter()
+            .map(|s| {
+                let spans = [s.span, s.pattern_span, s.function_span];
+                let args = [s.expects_table, s.expects_docstring];
+                (s.kind, s.regex.clone(), s.pattern_kind, s.line, s.function.clone(), s.module.clone(), s.captures.clone(), spans, args, s.notes.clone())
+            })
+            .collect();
+        v.sort_by(|a, b| a.3.cmp(&b.3).then(a.1.cmp(&b.1)));
+        v
+    }
+
+    #[test]
+    fn test_matches_scanner_on_common_forms() {
+        let sf = file(
+            r##"
+            fn register(registry: &mut Registry) {
+                registry.given(r"^I have (\d+) cukes$");
+                registry.when("^I eat (.*)$").then::<T>(r#"^a "quoted" word$"#);
+            }
+            /// Starts it.
+            given!(r"^start$", || {});
+            cucumber::when!("^middle$", || {});
+            #[then(regex = r"^done (?P<n>\d+) (\w+)$")]
+            fn ok(w: &mut World, step: &Step, n: usize, s: HashMap<u8, String>) { let _ = step.table.as_ref(); }
+            /// Counts cukes:
+            ///
+            /// - at least one
+            #[given(
+                expr = "I have {int} cuke(s)"
+            )]
+            #[doc = "Any number."]
+            async fn cukes(
+                world: &mut World,
+                count: i64,
+            ) {}
+            #[when("exact text")]
+            pub fn exact() {}
+            const AMOUNT: &str = r"(\d+)";
+            #[given(regex = concat!("^I owe ", AMOUNT, "$"))]
+            fn owe() {}
+            fn more(registry: &mut Registry) { registry.then(MISSING); }
+            mod nested {
+                mod r#inner { #[then("inside")] fn inside(w: &mut World) {} }
+                given!("^beside$", || {});
+            }
+            "##,
+        );
+        let consts = ConstTable::from_files(std::slice::from_ref(&sf));
+        let found = extract_file(&sf, &consts, &Detectors::default()).expect("file parses");
+        let scanned = scan_file(&sf, &consts, &Detectors::default());
+        let syn_steps = found.steps;
+        assert!(syn_steps.iter().all(|s| s.extractor == Some(Extractor::Syn)));
+        assert_eq!(summary(&syn_steps), summary(&scanned.steps));
+        assert_eq!(found.warnings, scanned.warnings);
+        assert_eq!(found.warnings.iter().map(|w| w.function.as_deref()).collect::<Vec<_>>(), vec![Some("owe")]);
+        assert!(syn_steps.iter().any(|s| s.regex == r"^I owe (\d+)$" && s.pattern_kind == PatternKind::Regex));
+        assert!(syn_steps.iter().any(|s| s.regex == "MISSING" && s.unresolved.is_some()));
+        assert!(syn_steps.iter().any(|s| s.regex == "inside" && s.module.as_deref() == Some("crate::steps::nested::inner")));
+        assert!(syn_steps.iter().any(|s| s.function.as_deref() == Some("ok") && s.expects_table == Some(true) && s.expects_docstring == Some(false)));
+        assert!(syn_steps.iter().any(|s| s.function.as_deref() == Some("cukes") && s.notes.as_deref() == Some("Counts cukes:\n\n- at least one\nAny number.")));
+        assert!(syn_steps.iter().any(|s| s.regex == "^beside$" && s.module.as_deref() == Some("crate::steps::nested")));
+    }
+
+    #[test]
+    fn test_method_steps_match_scanner() {
+        let sf = file(
+            r#"
+            impl World {
+                #[given(regex = r"^(\d+) cukes in (\w+)$")]
+                fn cukes(&mut self, n: usize, jar: String) {}
+                #[when(expr = "I eat {int}")]
+                async fn eat(self: &mut Self, step: &Step, n: u32) {}
+                #[then(regex = r"^(\d+) left$")]
+                fn left(&self) {}
+            }
+            #[then(regex = r"^(\d+) (\w+) remain$")]
+            fn remain(w: &mut World, n: usize) {}
+            "#,
+        );
+        let consts = ConstTable::from_files(std::slice::from_ref(&sf));
+        let found = extract_file(&sf, &consts, &Detectors::default()).expect("file parses");
+        let scanned = scan_file(&sf, &consts, &Detectors::default());
+        assert_eq!(summary(&found.steps), summary(&scanned.steps));
+        assert_eq!(found.warnings, scanned.warnings);
+        let params = |function: &str| {
+            let step = found.steps.iter().find(|s| s.function.as_deref() == Some(function)).expect("step found");
+            step.captures.iter().flatten().map(|c| c.name.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(params("cukes"), vec![Some("n".to_string()), Some("jar".to_string())]);
+        assert_eq!(params("eat"), vec![Some("n".to_string())]);
+        let mismatched: Vec<_> = found.warnings.iter().map(|w| w.function.as_deref()).collect();
+        assert_eq!(mismatched, vec![Some("left"), Some("remain")]);
+    }
+
+    #[test]
+    fn test_configured_names_match_scanner() {
+        let sf = file(
+            r#"
+            fn register(r: &mut Registry) { r.on_given("^built$"); r.step(StepType::When, "^chosen$"); }
+            step!(Then, r"^(\d+) left$", |w, n: usize| {});
+            #[given_async("async one")]
+            async fn one(w: &mut World) {}
+            #[step(When, regex = r"^attr (\w+)$")]
+            fn two(w: &mut World, s: String) {}
+            "#,
+        );
+        let config: DetectorConfig = serde_json::from_str(
+            r#"{ "macros": [{ "name": "step" }], "attributes": [{ "name": "given_async", "kind": "Given" }, { "name": "step" }],
+                 "methods": [{ "name": "on_given", "kind": "Given" }, { "name": "step" }] }"#,
+        )
+        .expect("valid config");
+        let detectors = Detectors::from_config(&config);
+        let consts = ConstTable::from_files(std::slice::from_ref(&sf));
+        let found = extract_file(&sf, &consts, &detectors).expect("file parses");
+        let scanned = scan_file(&sf, &consts, &detectors);
+        assert_eq!(found.steps.len(), 5);
+        assert_eq!(summary(&found.steps), summary(&scanned.steps));
+        let kinds: Vec<_> = summary(&found.steps).into_iter().map(|s| (s.0, s.1)).collect();
+        assert!(kinds.contains(&(StepKind::When, "^chosen$".into())));
+        assert!(kinds.contains(&(StepKind::Then, r"^(\d+) left$".into())));
+        assert!(kinds.contains(&(StepKind::When, r"^attr (\w+)$".into())));
+        assert!(extract_file(&sf, &consts, &Detectors::default()).expect("file parses").steps.is_empty());
+    }
+
+    #[test]
+    fn test_dropped_steps_match_scanner() {
+        let sf = file(
+            r#"
+            given!(pattern(), || {});
+            step!(Later, "^not a kind$", |w| {});
+            #[then]
+            fn bare(w: &mut World) {}
+            #[when(regex = make_pattern())]
+            fn made(w: &mut World) {}
+            fn register(r: &mut Registry) { r.given(handler); }
+            "#,
+        );
+        let config: DetectorConfig = serde_json::from_str(r#"{ "macros": [{ "name": "step" }] }"#).expect("valid config");
+        let detectors = Detectors::from_config(&config);
+        let consts = ConstTable::from_files(std::slice::from_ref(&sf));
+        let mut found = extract_file(&sf, &consts, &detectors).expect("file parses").warnings;
+        let mut scanned = scan_file(&sf, &consts, &detectors).warnings;
+        found.sort_by_key(|w| w.line);
+        scanned.sort_by_key(|w| w.line);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found, scanned);
+        assert_eq!(found[2].function.as_deref(), Some("bare"));
+    }
+
+    #[test]
+    fn test_builder_literal_on_next_line() {
+        let sf = file("fn f() {\n    registry\n        .given(\n            r\"^wrapped$\",\n            handler,\n        );\n}\n");
+        let steps = extract(&sf).expect("file parses");
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].regex, "^wrapped$");
+        assert_eq!(steps[0].line, 3);
+    }
+
+    #[test]
+    fn test_ignores_lookalikes_in_macro_bodies() {
+        let sf = file(
+            r#"
+            macro_rules! fake { () => { given!(r"^no$", || {}); registry.when("^no$"); } }
+            fn f() { let _ = "given!(\"^no$\")"; assert!(registry.then("^yes$").is_ok()); }
+            "#,
+        );
+        let steps = extract(&sf).expect("file parses");
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].regex, "^yes$");
+    }
+
+    #[test]
+    fn test_unparsable_file_falls_back_to_scanner() {
+        let sf = file("registry.given(r\"^top level$\");");
+        assert!(extract(&sf).is_none());
+        let idx = crate::step_index::extract_step_index_from_files(&[sf], &Detectors::default());
+        assert_eq!(idx.steps.len(), 1);
+        assert_eq!(idx.steps[0].extractor, Some(Extractor::Scanner));
+    }
+
+    #[test]
+    fn test_spans_account_for_bom_and_shebang() {
+        let sf = file("\u{feff}#!/usr/bin/env run-cargo-script\n#[given(\"ünïcode\")]\nfn f(w: &mut W) {}\n");
+        let steps = extract(&sf).expect("file parses");
+        let span = steps[0].pattern_span.expect("pattern span");
+        assert_eq!(&sf.text[span.start..span.end], "\"ünïcode\"");
+        assert_eq!((span.start_line, span.start_col, span.end_col), (2, 8, 17));
+        assert_eq!(steps[0].line, 2);
+    }
+}
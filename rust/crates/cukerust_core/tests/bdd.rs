@@ -5,6 +5,7 @@ use std::path::PathBuf;
 pub struct CoreWorld {
     pub files: Vec<cukerust_core::step_index::SourceFile>,
     pub index: Option<cukerust_core::step_index::StepIndex>,
+    pub detectors: cukerust_core::detectors::DetectorConfig,
 }
 
 mod steps;
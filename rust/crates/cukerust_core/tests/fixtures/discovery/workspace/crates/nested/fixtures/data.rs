@@ -0,0 +1 @@
+// ignored by the nested .gitignore
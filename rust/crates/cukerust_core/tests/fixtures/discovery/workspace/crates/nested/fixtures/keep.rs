@@ -0,0 +1 @@
+// re-included by the nested .gitignore
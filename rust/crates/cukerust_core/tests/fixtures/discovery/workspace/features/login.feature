@@ -0,0 +1,3 @@
+Feature: Login
+  Scenario: ok
+    Given a logged in user
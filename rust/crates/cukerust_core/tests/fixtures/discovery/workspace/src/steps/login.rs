@@ -0,0 +1,4 @@
+use cucumber::given;
+
+#[given("a logged in user")]
+fn logged_in(world: &mut World) {}
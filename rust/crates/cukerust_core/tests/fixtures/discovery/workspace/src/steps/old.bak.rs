@@ -0,0 +1 @@
+// backup
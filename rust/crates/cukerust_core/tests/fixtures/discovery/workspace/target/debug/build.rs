@@ -0,0 +1 @@
+// build output
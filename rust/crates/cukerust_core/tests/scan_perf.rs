@@ -0,0 +1,114 @@
+//! Static scan throughput over a generated monorepo-like corpus.
+//!
+//! The README targets a cold scan of ≤15k files in ≤2s and an incremental update in
+//! ≤200ms. The scan budget assumes the `parallel` feature on 4 cores, as on CI's
+//! `rust-perf` job; a single core takes about 3.5s. Check both with an optimised build:
+//!
+//! ```sh
+//! cargo test --release -p cukerust_core --features parallel --test scan_perf -- --ignored --nocapture
+//! ```
+
+use std::time::{Duration, Instant};
+
+use cukerust_core::detectors::Detectors;
+use cukerust_core::indexer::StepIndexer;
+use cukerust_core::step_index::{extract_step_index_from_files, SourceFile};
+
+/// Steps defined by each generated file.
+const STEPS_PER_FILE: usize = 5;
+
+/// `n` step files spread over 50 crates, each mixing attribute, builder and macro steps
+/// with const patterns, doc comments and filler code.
+fn corpus(n: usize) -> Vec<SourceFile> {
+    (0..n)
+        .map(|i| SourceFile { path: format!("crates/c{}/src/steps/s{i}.rs", i % 50), text: step_file(i) })
+        .collect()
+}
+
+fn step_file(i: usize) -> String {
+    let mut text = format!(
+        r##"//! Generated steps module {i}.
+use cucumber::{{given, then, when}};
+use crate::World;
+
+pub const AMOUNT_{i}: &str = r"(\d+)";
+
+/// Sets up account {i}.
+///
+/// Credits are added to the running total.
+#[given(regex = r"^account {i} has (\d+) credits$")]
+async fn account_{i}(world: &mut World, credits: usize) {{
+    world.total += credits;
+}}
+
+#[when(expr = "user {i} spends {{int}} credits")]
+fn spend_{i}(world: &mut World, step: &Step, n: i64) {{
+    let _ = step.table.as_ref();
+    world.total -= n as usize;
+}}
+
+#[then("the ledger {i} is closed")]
+fn closed_{i}(world: &mut World) {{}}
+
+pub fn register_{i}(registry: &mut Registry) {{
+    // not a step: "given!(\"^nope$\")"
+    registry
+        .then(concat!("^balance {i} is ", AMOUNT_{i}, "$"), |w, n: usize| {{}});
+}}
+
+given!(r#"^a "quoted" macro step {i}$"#, || {{}});
+"##
+    );
+    for j in 0..20 {
+        text.push_str(&format!(
+            "\nfn filler_{i}_{j}(values: &[u32]) -> Vec<String> {{\n    /* block {j} */ values.iter().map(|v| format!(\"{{v}}-{j}\")).collect()\n}}\n"
+        ));
+    }
+    text
+}
+
+#[test]
+fn corpus_extraction_is_complete_and_deterministic() {
+    let files = corpus(200);
+    let mut first = extract_step_index_from_files(&files, &Detectors::default());
+    assert_eq!(first.stats.total, files.len() * STEPS_PER_FILE);
+    assert_eq!(first.stats.ambiguous, 0);
+    first.find_overlaps();
+    assert!(first.stats.overlaps.is_empty(), "{:?}", first.stats.overlaps);
+    assert!(first.steps.iter().all(|s| s.unresolved.is_none()));
+    assert!(first.steps.windows(2).all(|w| (&w[0].file, w[0].line) <= (&w[1].file, w[1].line)));
+    let second = extract_step_index_from_files(&files, &Detectors::default());
+    assert_eq!(first.steps, second.steps);
+    assert_eq!(first.warnings, second.warnings);
+}
+
+#[test]
+#[ignore = "timing target; run with --release"]
+fn scans_15k_files_within_2s() {
+    let files = corpus(15_000);
+    let bytes: usize = files.iter().map(|f| f.text.len()).sum();
+    let t0 = Instant::now();
+    let index = extract_step_index_from_files(&files, &Detectors::default());
+    let elapsed = t0.elapsed();
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    println!("scanned {} files ({} MiB) into {} steps in {elapsed:?} on {cores} cores", files.len(), bytes >> 20, index.stats.total);
+    assert_eq!(index.stats.total, files.len() * STEPS_PER_FILE);
+    assert!(elapsed <= Duration::from_secs(2), "cold scan took {elapsed:?} on {cores} cores (the budget assumes 4)");
+}
+
+#[test]
+#[ignore = "timing target; run with --release"]
+fn upserts_one_file_into_15k_within_200ms() {
+    let mut files = corpus(15_000);
+    let mut indexer = StepIndexer::default();
+    indexer.upsert_files(files.clone());
+    let edited = &mut files[7_500];
+    edited.text = edited.text.replace("credits$", "coins$");
+    let t0 = Instant::now();
+    let delta = indexer.upsert_file(edited.clone());
+    let elapsed = t0.elapsed();
+    println!("upserted 1 of {} files in {elapsed:?}", files.len());
+    assert_eq!((delta.added.len(), delta.removed.len()), (1, 1));
+    assert_eq!(delta.stats.total, files.len() * STEPS_PER_FILE);
+    assert!(elapsed <= Duration::from_millis(200), "upsert took {elapsed:?}");
+}
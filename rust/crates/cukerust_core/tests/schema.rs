@@ -0,0 +1,192 @@
+//! The committed `schema/step_index.schema.json` matches [`step_index_schema`] and
+//! describes what the extractor writes: every key it writes is declared, and the sample
+//! indexes below use every declared key. Regenerate it after changing the artifact format:
+//!
+//! ```sh
+//! CUKERUST_UPDATE_SCHEMA=1 cargo test -p cukerust_core --test schema
+//! ```
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use cukerust_core::detectors::Detectors;
+use cukerust_core::schema::step_index_schema;
+use cukerust_core::step_index::{extract_step_index_from_files, SourceFile, StepIndex};
+use serde_json::{json, Value};
+
+fn schema_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("schema/step_index.schema.json")
+}
+
+#[test]
+fn committed_schema_is_current() {
+    let generated = serde_json::to_string_pretty(&step_index_schema()).unwrap() + "\n";
+    if std::env::var_os("CUKERUST_UPDATE_SCHEMA").is_some() {
+        std::fs::write(schema_path(), &generated).unwrap();
+    }
+    let committed = std::fs::read_to_string(schema_path()).unwrap_or_default();
+    assert!(committed == generated, "{} is stale; rerun with CUKERUST_UPDATE_SCHEMA=1", schema_path().display());
+}
+
+const STEPS: &str = r##"use cucumber::{given, then, when, Parameter};
+
+#[derive(Parameter)]
+#[param(name = "animal", regex = "cat|dog")]
+enum Animal { Cat, Dog }
+
+/// Feeds an animal.
+#[given(expr = "a hungry {animal}")]
+async fn hungry(w: &mut World, animal: Animal) {}
+
+#[given(regex = r"^(?P<n>\d+) (\w+)$")]
+async fn counted(w: &mut World, n: usize, what: String) {}
+
+#[given(regex = r"^(?P<n>\d+) (\w+)$")]
+async fn counted_again(w: &mut World, n: usize) {}
+
+#[when("the bell rings")]
+async fn bell(w: &mut World, step: &Step) {}
+
+#[then(regex = r"^broken (\d+$")]
+async fn broken(w: &mut World, n: usize) {}
+
+#[then(regex = r"^it (\d+)$")]
+async fn digits(w: &mut World, n: usize) {}
+
+fn builder() {
+    World::cucumber().then(r"^it (\w+)$", |w, s| {}).when(ELSEWHERE, |w| {});
+}
+"##;
+
+fn extracted() -> Value {
+    let files = vec![SourceFile { path: "tests/steps/zoo.rs".into(), text: STEPS.into() }];
+    let mut index = extract_step_index_from_files(&files, &Detectors::default());
+    index.find_overlaps();
+    assert!(!index.warnings.is_empty() && !index.parameters.is_empty() && !index.stats.ambiguities.is_empty());
+    serde_json::to_value(index).unwrap()
+}
+
+/// A version 1 artifact, migrated; `tags` only come from artifacts.
+fn migrated() -> Value {
+    let v1 = r#"{
+        "steps": [{ "kind": "Given", "regex": "^(\\d+) (\\w+)$", "file": "src/a.rs", "line": 1, "captures": ["n", "what"], "tags": ["@slow"] }],
+        "stats": { "total": 1, "by_kind": { "Given": 1, "When": 0, "Then": 0 }, "ambiguous": 0 }
+    }"#;
+    let loaded = StepIndex::load_artifact(v1).unwrap();
+    assert_eq!(loaded.from_version, 1);
+    assert!(loaded.rejected.is_empty());
+    serde_json::to_value(loaded.index).unwrap()
+}
+
+#[test]
+fn extracted_index_matches_schema() {
+    let schema = step_index_schema();
+    let doc = extracted();
+    assert_eq!(validate(&schema, &doc), Vec::<String>::new());
+
+    let mut bad = doc.clone();
+    bad["steps"][0]["kind"] = json!("Gvien");
+    bad["stats"].as_object_mut().unwrap().remove("total");
+    bad["schema_version"] = json!(1);
+    bad["steps"][0]["priority"] = json!(1);
+    let errors = vec!["/schema_version: not 2", "/stats: missing total", "/steps/0/kind: not one of the enum values", "/steps/0/priority: not declared in the schema"];
+    assert_eq!(validate(&schema, &bad), errors);
+}
+
+#[test]
+fn migrated_artifact_matches_schema() {
+    assert_eq!(validate(&step_index_schema(), &migrated()), Vec::<String>::new());
+}
+
+#[test]
+fn samples_use_every_declared_key() {
+    fn keys(value: &Value, out: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(object) => object.iter().for_each(|(key, item)| {
+                out.insert(key.clone());
+                keys(item, out);
+            }),
+            Value::Array(items) => items.iter().for_each(|item| keys(item, out)),
+            _ => {}
+        }
+    }
+    fn declared(schema: &Value, out: &mut BTreeSet<String>) {
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            out.extend(properties.keys().cloned());
+        }
+        schema.as_object().into_iter().flat_map(|o| o.values()).for_each(|item| declared(item, out));
+    }
+    let mut used = BTreeSet::new();
+    keys(&extracted(), &mut used);
+    keys(&migrated(), &mut used);
+    let mut all = BTreeSet::new();
+    declared(&step_index_schema(), &mut all);
+    assert_eq!(all.difference(&used).collect::<Vec<_>>(), Vec::<&String>::new());
+}
+
+/// The errors of `value` against `schema`, as `pointer: problem`. Covers the keywords
+/// the Step Index schema uses.
+fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, schema, value, "", &mut errors);
+    errors
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let target = root.pointer(reference.trim_start_matches('#')).unwrap_or_else(|| panic!("dangling $ref {reference}"));
+        return check(root, target, value, at, errors);
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            errors.push(format!("{at}: not {expected}"));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!("{at}: not one of the enum values"));
+        }
+    }
+    if let Some(ty) = schema.get("type").and_then(Value::as_str) {
+        let ok = match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_u64() || value.is_i64(),
+            other => panic!("unsupported type {other}"),
+        };
+        if !ok {
+            return errors.push(format!("{at}: not of type {ty}"));
+        }
+    }
+    if let Some(minimum) = schema.get("minimum").and_then(Value::as_i64) {
+        if value.as_i64().is_some_and(|v| v < minimum) {
+            errors.push(format!("{at}: below {minimum}"));
+        }
+    }
+    if let (Some(items), Some(values)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in values.iter().enumerate() {
+            check(root, items, item, &format!("{at}/{i}"), errors);
+        }
+    }
+    let Some(object) = value.as_object() else {
+        return;
+    };
+    for name in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+        if !object.contains_key(name.as_str().unwrap()) {
+            errors.push(format!("{at}: missing {}", name.as_str().unwrap()));
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, item) in object {
+        let pointer = format!("{at}/{key}");
+        match (properties.and_then(|p| p.get(key)), schema.get("additionalProperties")) {
+            (Some(property), _) => check(root, property, item, &pointer, errors),
+            (None, Some(extra)) => check(root, extra, item, &pointer, errors),
+            // the schema allows unknown keys for readers; what we write must be declared
+            (None, None) => errors.push(format!("{pointer}: not declared in the schema")),
+        }
+    }
+}
+
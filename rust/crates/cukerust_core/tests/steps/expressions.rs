@@ -0,0 +1,24 @@
+use cucumber::then;
+use cukerust_core::cucumber_expression::to_regex;
+use regex::Regex;
+use crate::CoreWorld;
+
+fn compile(expression: &str) -> Regex {
+    let pattern = to_regex(expression).expect("expression compiles");
+    Regex::new(&pattern).expect("expression regex compiles")
+}
+
+#[then(regex = r#"^the expression "(.*)" matches "(.*)"$"#)]
+async fn expression_matches(_world: &mut CoreWorld, expression: String, text: String) {
+    assert!(compile(&expression).is_match(&text), "expected {expression:?} to match {text:?}");
+}
+
+#[then(regex = r#"^the expression "(.*)" does not match "(.*)"$"#)]
+async fn expression_does_not_match(_world: &mut CoreWorld, expression: String, text: String) {
+    assert!(!compile(&expression).is_match(&text), "expected {expression:?} not to match {text:?}");
+}
+
+#[then(regex = r#"^the expression "(.*)" fails to compile$"#)]
+async fn expression_fails(_world: &mut CoreWorld, expression: String) {
+    assert!(to_regex(&expression).is_err(), "expected {expression:?} to be rejected");
+}
@@ -1 +1,2 @@
+pub mod expressions;
 pub mod parsing;
@@ -1,5 +1,6 @@
 use cucumber::{given, then, when};
 use cucumber::gherkin::Step;
+use cukerust_core::detectors::Detectors;
 use cukerust_core::step_index::{extract_step_index_from_files, SourceFile};
 use crate::CoreWorld;
 
@@ -97,7 +98,14 @@ async fn a_file_with_raw_string_multi(world: &mut CoreWorld) {
 
 #[when("we extract the Step Index")]
 async fn we_extract_step_index(world: &mut CoreWorld) {
-    let idx = extract_step_index_from_files(&world.files);
+    let idx = extract_step_index_from_files(&world.files, &Detectors::from_config(&world.detectors));
+    world.index = Some(idx);
+}
+
+#[when("we extract the Step Index with overlaps")]
+async fn we_extract_step_index_with_overlaps(world: &mut CoreWorld) {
+    let mut idx = extract_step_index_from_files(&world.files, &Detectors::from_config(&world.detectors));
+    idx.find_overlaps();
     world.index = Some(idx);
 }
 
@@ -121,6 +129,54 @@ async fn ambiguous_count(world: &mut CoreWorld, n: usize) {
     assert_eq!(idx.stats.ambiguous, n);
 }
 
+#[then(regex = r#"^there is a (Given|When|Then) overlap with witness \"(.*)\" between:$"#)]
+async fn there_is_overlap(world: &mut CoreWorld, kind: String, witness: String, step: &Step) {
+    let idx = world.index.as_ref().expect("index built");
+    let table = step.table.as_ref().expect("locations table");
+    let expected: Vec<(String, usize)> = table.rows.iter().skip(1).map(|r| (r[0].clone(), r[1].parse().unwrap())).collect();
+    let found = idx.stats.overlaps.iter().any(|o| {
+        let at: Vec<(String, usize)> = o.steps.iter().map(|s| (s.file.clone(), s.line)).collect();
+        format!("{:?}", o.kind) == kind && o.witness == witness && at == expected
+    });
+    assert!(found, "expected overlap not found in {:?}", idx.stats.overlaps);
+}
+
+#[then(regex = r"^there are (\d+) overlaps$")]
+async fn there_are_n_overlaps(world: &mut CoreWorld, n: usize) {
+    let idx = world.index.as_ref().expect("index built");
+    assert_eq!(idx.stats.overlaps.len(), n, "overlaps: {:?}", idx.stats.overlaps);
+}
+
+#[then(regex = r#"^there is a (Given|When|Then) ambiguity for \"(.*)\" at:$"#)]
+async fn there_is_ambiguity(world: &mut CoreWorld, kind: String, regex: String, step: &Step) {
+    let idx = world.index.as_ref().expect("index built");
+    let table = step.table.as_ref().expect("locations table");
+    let expected: Vec<(String, usize, Option<String>)> =
+        table.rows.iter().skip(1).map(|r| (r[0].clone(), r[1].parse().unwrap(), Some(r[2].clone()).filter(|f| !f.is_empty()))).collect();
+    let group = idx
+        .stats
+        .ambiguities
+        .iter()
+        .find(|a| format!("{:?}", a.kind) == kind && a.regex == regex)
+        .unwrap_or_else(|| panic!("no ambiguity for {regex} in {:?}", idx.stats.ambiguities));
+    let actual: Vec<(String, usize, Option<String>)> = group.steps.iter().map(|s| (s.file.clone(), s.line, s.function.clone())).collect();
+    assert_eq!(actual, expected);
+}
+
+#[then(regex = r#"^file \"([^\"]+)\" has (\d+) steps, (\d+) of them ambiguous$"#)]
+async fn file_stats(world: &mut CoreWorld, file: String, steps: usize, ambiguous: usize) {
+    let idx = world.index.as_ref().expect("index built");
+    let stats = idx.stats.by_file.get(&file).expect("file stats");
+    assert_eq!((stats.steps, stats.ambiguous), (steps, ambiguous));
+}
+
+#[then(regex = r"^the steps by detector are (\d+) Attribute, (\d+) Macro, (\d+) Builder and (\d+) Other$")]
+async fn detector_stats(world: &mut CoreWorld, attribute: usize, macros: usize, builder: usize, other: usize) {
+    let idx = world.index.as_ref().expect("index built");
+    let d = &idx.stats.by_detector;
+    assert_eq!((d.attribute, d.macros, d.builder, d.other), (attribute, macros, builder, other));
+}
+
 // Generic Given: push any Rust file content via DocString
 #[given(regex = r#"^a Rust file \"([^\"]+)\" with content:$"#)]
 async fn a_rust_file_with_content(world: &mut CoreWorld, path: String, step: &Step) {
@@ -128,6 +184,12 @@ async fn a_rust_file_with_content(world: &mut CoreWorld, path: String, step: &St
     world.files.push(SourceFile { path, text });
 }
 
+#[given("the detector config:")]
+async fn the_detector_config(world: &mut CoreWorld, step: &Step) {
+    let json = step.docstring.clone().unwrap_or_default();
+    world.detectors = serde_json::from_str(&json).expect("valid detector config");
+}
+
 #[then("there are no steps")]
 async fn there_are_no_steps(world: &mut CoreWorld) {
     let idx = world.index.as_ref().expect("index built");
@@ -204,3 +266,154 @@ async fn there_is_step_with_kind_exact_regex_file_line(
         .any(|s| s.kind == target_kind && s.regex == expected && s.file == file && s.line == line);
     assert!(found, "expected step not found with exact regex (including newlines)");
 }
+
+#[then(regex = r#"^the step with regex \"(.*)\" has pattern kind \"(Regex|Literal|Expression)\"$"#)]
+async fn step_has_pattern_kind(world: &mut CoreWorld, regex: String, pattern_kind: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let step = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert_eq!(format!("{:?}", step.pattern_kind), pattern_kind);
+}
+
+#[then(regex = r#"^there is an unresolved step \"(.*)\" from file \"([^\"]+)\" at line (\d+) because \"(.*)\"$"#)]
+async fn there_is_unresolved_step(world: &mut CoreWorld, regex: String, file: String, line: usize, reason: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let found = idx
+        .steps
+        .iter()
+        .any(|s| s.regex == regex && s.file == file && s.line == line && s.unresolved.as_deref() == Some(reason.as_str()));
+    assert!(found, "expected unresolved step not found: regex={regex} file={file}:{line} reason={reason}");
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" is invalid because \"(.*)\"$"#)]
+async fn step_is_invalid(world: &mut CoreWorld, regex: String, reason: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert_eq!(entry.invalid.as_deref(), Some(reason.as_str()));
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" is valid$"#)]
+async fn step_is_valid(world: &mut CoreWorld, regex: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert!(entry.invalid.is_none(), "unexpected invalid: {:?}", entry.invalid);
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" has captures:$"#)]
+async fn step_has_captures(world: &mut CoreWorld, regex: String, step: &Step) {
+    use cukerust_core::step_index::Capture;
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    let table = step.table.as_ref().expect("captures table");
+    let expected: Vec<Capture> = table
+        .rows
+        .iter()
+        .skip(1)
+        .map(|r| {
+            let opt = |s: &String| if s.is_empty() { None } else { Some(s.clone()) };
+            Capture { index: r[0].parse().unwrap(), name: opt(&r[1]), ty: opt(&r[2]), start: r[3].parse().unwrap(), end: r[4].parse().unwrap() }
+        })
+        .collect();
+    let actual: Vec<Capture> = entry.captures.clone().unwrap_or_default();
+    assert_eq!(actual, expected);
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" has no captures$"#)]
+async fn step_has_no_captures(world: &mut CoreWorld, regex: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert!(entry.captures.is_none(), "unexpected captures: {:?}", entry.captures);
+}
+
+#[then(regex = r#"^there is a \"(\w+)\" warning for file \"([^\"]+)\" at line (\d+): \"(.*)\"$"#)]
+async fn there_is_warning(world: &mut CoreWorld, kind: String, file: String, line: usize, message: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let found = idx
+        .warnings
+        .iter()
+        .any(|w| format!("{:?}", w.kind) == kind && w.file == file && w.line == line && w.message == message);
+    assert!(found, "expected warning not found in {:?}", idx.warnings);
+}
+
+#[then(regex = r#"^the warning at line (\d+) comes from the \"(\w+)\" detector$"#)]
+async fn warning_detector(world: &mut CoreWorld, line: usize, detector: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let warning = idx.warnings.iter().find(|w| w.line == line).expect("warning at line");
+    assert_eq!(warning.detector.map(|d| format!("{d:?}")), Some(detector), "warning: {warning:?}");
+}
+
+#[then(regex = r"^there are (\d+) warnings$")]
+async fn there_are_n_warnings(world: &mut CoreWorld, n: usize) {
+    let idx = world.index.as_ref().expect("index built");
+    assert_eq!(idx.warnings.len(), n, "warnings: {:?}", idx.warnings);
+}
+
+#[then(regex = r#"^there is a custom parameter \"([^\"]+)\" for type \"([^\"]+)\" with regex \"(.*)\" at line (\d+)$"#)]
+async fn there_is_custom_parameter(world: &mut CoreWorld, name: String, type_name: String, regex: String, line: usize) {
+    let idx = world.index.as_ref().expect("index built");
+    let found = idx
+        .parameters
+        .iter()
+        .any(|p| p.name == name && p.type_name == type_name && p.regex == regex && p.line == line);
+    assert!(found, "expected custom parameter not found in {:?}", idx.parameters);
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" is in module \"([^\"]+)\"(?: with function \"([^\"]+)\")?$"#)]
+async fn step_is_in_module(world: &mut CoreWorld, regex: String, module: String, function: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert_eq!(entry.module.as_deref(), Some(module.as_str()));
+    if !function.is_empty() {
+        assert_eq!(entry.function.as_deref(), Some(function.as_str()));
+    }
+}
+
+#[then(regex = r#"^the (definition|pattern|function) span of the step with regex \"(.*)\" covers \"(.*)\" from (\d+):(\d+) to (\d+):(\d+)$"#)]
+#[allow(clippy::too_many_arguments)]
+async fn step_span_covers(
+    world: &mut CoreWorld,
+    which: String,
+    regex: String,
+    text: String,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    let span = match which.as_str() {
+        "definition" => entry.span,
+        "pattern" => entry.pattern_span,
+        _ => entry.function_span,
+    }
+    .expect("span recorded");
+    let file = world.files.iter().find(|f| f.path == entry.file).expect("source file");
+    assert_eq!(file.text[span.start..span.end].replace('\n', "\\n"), text);
+    assert_eq!((span.start_line, span.start_col, span.end_line, span.end_col), (start_line, start_col, end_line, end_col));
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" expects a table \"(yes|no|unknown)\" and a docstring \"(yes|no|unknown)\"$"#)]
+async fn step_expects_args(world: &mut CoreWorld, regex: String, table: String, docstring: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    let usage = |v: Option<bool>| match v {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "unknown",
+    };
+    assert_eq!((usage(entry.expects_table), usage(entry.expects_docstring)), (table.as_str(), docstring.as_str()));
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" has notes \"(.*)\"$"#)]
+async fn step_has_notes(world: &mut CoreWorld, regex: String, notes: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert_eq!(entry.notes.as_deref().map(|n| n.replace('\n', "\\n")), Some(notes));
+}
+
+#[then(regex = r#"^the step with regex \"(.*)\" has no notes$"#)]
+async fn step_has_no_notes(world: &mut CoreWorld, regex: String) {
+    let idx = world.index.as_ref().expect("index built");
+    let entry = idx.steps.iter().find(|s| s.regex == regex).expect("step with regex");
+    assert_eq!(entry.notes, None);
+}
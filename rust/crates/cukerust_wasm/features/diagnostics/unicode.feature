@@ -0,0 +1,65 @@
+Feature: Non-English step patterns end to end
+
+  Scenario: Umlauts, Japanese and emoji patterns match their feature steps
+    Given a Rust file "src/steps.rs" with content:
+      """
+      // Schritte für Gurken – ÄÖÜ
+      #[given(regex = r"^ich habe (\d+) Gurken für Jürgen$")]
+      fn gurken(world: &mut World, n: usize) {}
+
+      #[when("キュウリを食べる")]
+      fn taberu(world: &mut World) {}
+
+      /* 🥒 block comment ☃ */ then!("^der Korb ist l\u{e4}nger als (\\d+) cm$", |w, n| {});
+
+      #[then(expr = "I see {int} 🥒")]
+      fn emoji(world: &mut World, n: i64) {}
+      """
+    And a feature file with content:
+      """
+      Feature: Gurken
+        Scenario: Unterwegs
+          Given ich habe 3 Gurken für Jürgen
+          When キュウリを食べる
+          Then der Korb ist länger als 12 cm
+          And I see 4 🥒
+      """
+    When we compute diagnostics for the feature
+    Then there are no diagnostics
+
+  Scenario: Spanish dialect with accented patterns
+    Given a Rust file "src/pasos.rs" with content:
+      """
+      #[given(regex = r"^que tengo (\w+) pepinos en el jardín$")]
+      fn pepinos(world: &mut World, n: usize) {}
+      """
+    And a feature file with content:
+      """
+      # language: es
+      Característica: Pepinos
+        Escenario: Jardín
+          Dado que tengo 5 pepinos en el jardín
+          Y que tengo cinco pepinos en el jardín
+          Y que tengo 5 pepinos en la cocina
+      """
+    When we compute diagnostics for the feature
+    Then there are 2 diagnostics
+    And there is a diagnostic on line 5: "argument 'cinco' cannot be parsed as usize"
+    And the diagnostic "argument 'cinco' cannot be parsed as usize" spans columns 16 to 21
+    And there is a diagnostic on line 6: "Undefined step"
+
+  Scenario: Columns after multi-byte text count UTF-16 code units
+    Given a Rust file "src/steps.rs" with content:
+      """
+      #[given(regex = r"^🥒 für (\w+) Jürgen$")]
+      fn count(world: &mut World, n: usize) {}
+      """
+    And a feature file with content:
+      """
+      Feature: Columns
+        Scenario: Emoji
+          Given 🥒 für zwölf Jürgen
+      """
+    When we compute diagnostics for the feature
+    Then there is a diagnostic on line 3: "argument 'zwölf' cannot be parsed as usize"
+    And the diagnostic "argument 'zwölf' cannot be parsed as usize" spans columns 17 to 22
@@ -1,25 +1,163 @@
 use wasm_bindgen::prelude::*;
+use cukerust_core::artifact;
+use cukerust_core::cucumber_expression;
+use cukerust_core::detectors::{DetectorConfig, Detectors};
+use cukerust_core::parameters::{self, CustomParameter};
 use cukerust_core::step_index as core;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use regex::Regex;
 
 #[derive(Debug, Deserialize)]
 struct Input {
     files: Vec<core::SourceFile>,
+    /// Extra macro/attribute/method names to recognise as step definitions.
+    #[serde(default)]
+    detectors: DetectorConfig,
+    /// Also fill `stats.overlaps`, which costs more than the rest of the stats.
+    #[serde(default)]
+    overlaps: bool,
 }
 
-/// JSON FFI: accepts `{ files: Array<{ path, text }>}\n` and returns StepIndex JSON.
+/// JSON FFI: accepts `{ files: Array<{ path, text }>, detectors?, overlaps? }` and returns StepIndex JSON.
 #[wasm_bindgen]
 pub fn extract_step_index(input_json: &str) -> String {
     match serde_json::from_str::<Input>(input_json) {
         Ok(input) => {
-            let idx = core::extract_step_index_from_files(&input.files);
+            let mut idx = core::extract_step_index_from_files(&input.files, &Detectors::from_config(&input.detectors));
+            if input.overlaps {
+                idx.find_overlaps();
+            }
             serde_json::to_string(&idx).unwrap_or_else(|e| error_json(&format!("serde: {e}")))
         }
         Err(e) => error_json(&format!("input: {e}")),
     }
 }
 
+/// JSON FFI: loads a `step_index.json` artifact best-effort and returns `{ index, rejected }`,
+/// where `rejected` lists `{ pointer, reason }` for every entry left out; `{ error }` when the
+/// artifact is not a JSON object.
+#[wasm_bindgen]
+pub fn load_step_index(artifact_json: &str) -> String {
+    match core::StepIndex::load_artifact(artifact_json) {
+        Ok(loaded) => serde_json::to_string(&loaded).unwrap_or_else(|e| error_json(&format!("serde: {e}"))),
+        Err(e) => error_json(&format!("artifact: {e}")),
+    }
+}
+
+/// Steps that deserialize; the others are skipped like [`load_step_index`] does.
+fn lenient_steps<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<core::StepEntry>, D::Error> {
+    let values = Vec::<serde_json::Value>::deserialize(d)?;
+    Ok(artifact::entries("/steps", values, &mut Vec::new()))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct IndexerConfig {
+    #[serde(default)]
+    detectors: DetectorConfig,
+}
+
+/// Incremental Step Index for the extension: holds the files of a workspace folder and
+/// re-extracts only what a change affects. Updates return a delta
+/// `{ added, removed, changed, stats }` as JSON.
+#[wasm_bindgen]
+pub struct StepIndexer {
+    inner: cukerust_core::indexer::StepIndexer,
+}
+
+#[wasm_bindgen]
+impl StepIndexer {
+    /// Accepts `{ detectors? }`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config_json: &str) -> Result<StepIndexer, String> {
+        let config: IndexerConfig = serde_json::from_str(config_json).map_err(|e| format!("input: {e}"))?;
+        Ok(StepIndexer { inner: cukerust_core::indexer::StepIndexer::new(Detectors::from_config(&config.detectors)) })
+    }
+
+    /// Accepts `{ path, text }`.
+    pub fn upsert_file(&mut self, file_json: &str) -> String {
+        match serde_json::from_str::<core::SourceFile>(file_json) {
+            Ok(file) => to_json(&self.inner.upsert_file(file)),
+            Err(e) => error_json(&format!("input: {e}")),
+        }
+    }
+
+    /// Accepts `{ files: Array<{ path, text }> }`, e.g. for the initial scan.
+    pub fn upsert_files(&mut self, input_json: &str) -> String {
+        #[derive(Deserialize)]
+        struct Files {
+            files: Vec<core::SourceFile>,
+        }
+        match serde_json::from_str::<Files>(input_json) {
+            Ok(input) => to_json(&self.inner.upsert_files(input.files)),
+            Err(e) => error_json(&format!("input: {e}")),
+        }
+    }
+
+    pub fn remove_file(&mut self, path: &str) -> String {
+        to_json(&self.inner.remove_file(path))
+    }
+
+    /// The steps of one file as a JSON array.
+    pub fn file_steps(&self, path: &str) -> String {
+        to_json(&self.inner.file_steps(path))
+    }
+
+    /// The StepIndex `warnings` as a JSON array.
+    pub fn warnings(&self) -> String {
+        to_json(&self.inner.warnings())
+    }
+
+    /// The StepIndex `parameters` as a JSON array.
+    pub fn parameters(&self) -> String {
+        to_json(&self.inner.parameters())
+    }
+
+    /// The whole StepIndex JSON, as `extract_step_index` returns it.
+    pub fn snapshot(&self) -> String {
+        to_json(&self.inner.snapshot())
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|e| error_json(&format!("serde: {e}")))
+}
+
+#[derive(Debug, Serialize)]
+struct DefinitionDiagnosticOut {
+    file: String,
+    line: usize, // 0-based
+    message: String,
+    severity: &'static str, // "warning"
+}
+
+/// JSON FFI: accepts `{ files: Array<{ path, text }>, detectors? }` and returns definition-level
+/// diagnostics for the Rust sources as `{ diags }` (e.g. capture/parameter count mismatches,
+/// or step definitions that could not be extracted).
+#[wasm_bindgen]
+pub fn diagnostics_for_definitions(input_json: &str) -> String {
+    match serde_json::from_str::<Input>(input_json) {
+        Ok(input) => {
+            let idx = core::extract_step_index_from_files(&input.files, &Detectors::from_config(&input.detectors));
+            let diags = definition_diagnostics(&idx.warnings);
+            serde_json::to_string(&serde_json::json!({ "diags": diags }))
+                .unwrap_or_else(|e| error_json(&format!("serde: {e}")))
+        }
+        Err(e) => error_json(&format!("input: {e}")),
+    }
+}
+
+fn definition_diagnostics(warnings: &[core::StepWarning]) -> Vec<DefinitionDiagnosticOut> {
+    warnings
+        .iter()
+        .map(|w| DefinitionDiagnosticOut {
+            file: w.file.clone(),
+            line: w.line.saturating_sub(1),
+            message: w.message.clone(),
+            severity: "warning",
+        })
+        .collect()
+}
+
 fn error_json(msg: &str) -> String {
     serde_json::json!({ "error": msg }).to_string()
 }
@@ -38,6 +176,7 @@ struct MatchQuery {
 
 #[derive(Debug, Deserialize)]
 struct MatchInput {
+    #[serde(deserialize_with = "lenient_steps")]
     steps: Vec<core::StepEntry>,
     query: MatchQuery,
 }
@@ -58,7 +197,7 @@ pub fn match_steps(input_json: &str) -> String {
     let mut out: Vec<core::StepEntry> = Vec::new();
     for s in parsed.steps.into_iter() {
         if s.kind != kind { continue; }
-        let pattern = match pattern_for_mode(&s.regex, mode) {
+        let pattern = match pattern_for_step(&s, mode) {
             Some(p) => p,
             None => continue,
         };
@@ -71,6 +210,19 @@ pub fn match_steps(input_json: &str) -> String {
     serde_json::to_string(&out).unwrap_or_else(|e| error_json(&format!("serde: {e}")))
 }
 
+/// Literal steps and Cucumber Expressions always match the whole step text, regardless of `mode`.
+/// Steps whose pattern could not be resolved never match.
+fn pattern_for_step(step: &core::StepEntry, mode: &str) -> Option<String> {
+    if step.unresolved.is_some() {
+        return None;
+    }
+    match step.pattern_kind {
+        core::PatternKind::Regex => pattern_for_mode(&step.regex, mode),
+        core::PatternKind::Literal => Some(format!("^{}$", regex::escape(&step.regex))),
+        core::PatternKind::Expression => cucumber_expression::to_regex(&step.regex).ok(),
+    }
+}
+
 fn pattern_for_mode(regex: &str, mode: &str) -> Option<String> {
     match mode {
         "anchored" => {
@@ -101,7 +253,10 @@ struct DiagnosticsConfig {
 struct DiagnosticsInput {
     feature_text: String,
     config: Option<DiagnosticsConfig>,
+    #[serde(deserialize_with = "lenient_steps")]
     steps: Vec<core::StepEntry>,
+    #[serde(default)]
+    parameters: Vec<CustomParameter>,
 }
 
 #[derive(Debug, Serialize)]
@@ -109,6 +264,15 @@ struct DiagnosticOut {
     line: usize, // 0-based
     message: String,
     severity: &'static str, // "warning"
+    // 0-based UTF-16 column range within the line, when the diagnostic is narrower than it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_col: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_col: Option<usize>,
+}
+
+fn line_warning(line: usize, message: &str) -> DiagnosticOut {
+    DiagnosticOut { line, message: message.into(), severity: "warning", start_col: None, end_col: None }
 }
 
 #[wasm_bindgen]
@@ -126,38 +290,72 @@ pub fn diagnostics_for_feature(input_json: &str) -> String {
     let mut last_kind: Option<core::StepKind> = None;
     let mut diags: Vec<DiagnosticOut> = Vec::new();
     let mode = cfg.match_mode.as_deref().unwrap_or("smart");
+    let broken_steps = broken_definitions(&parsed.steps);
 
     for (i, line) in lines.iter().enumerate() {
         if let Some(cap) = step_re.captures(line) {
             let keyword = cap.get(1).map(|m| m.as_str()).unwrap_or("");
             let body = cap.get(2).map(|m| m.as_str()).unwrap_or("");
+            let body_start = cap.get(2).map(|m| m.start()).unwrap_or(0) + (body.len() - body.trim_start().len());
             let mut kind = kind_from_keyword(keyword, &dialect).unwrap_or(core::StepKind::Given);
-            if dialect.and.iter().any(|k| *k == keyword) || dialect.but.iter().any(|k| *k == keyword) {
+            if dialect.and.contains(&keyword) || dialect.but.contains(&keyword) {
                 if let Some(prev) = last_kind { kind = prev; }
             } else {
                 last_kind = Some(kind);
             }
+            let supplied = supplied_step_arg(&lines, i);
             let oc = extract_outline_context(&lines, i);
             if oc.is_outline && !oc.examples.is_empty() && body.contains('<') && body.contains('>') {
                 let mut any_ok = false;
                 let mut any_amb = false;
+                let mut arg_errors: Vec<String> = Vec::new();
+                let mut matched: Option<&core::StepEntry> = None;
+                let mut broken: Option<String> = None;
                 for row in oc.examples.iter() {
                     let resolved = resolve_placeholders(body, row);
                     let matches = match_one(&parsed.steps, kind, &resolved, mode);
                     if matches.len() > 1 { any_amb = true; }
                     if !matches.is_empty() { any_ok = true; }
+                    if matches.is_empty() && broken.is_none() {
+                        broken = broken_definition(&broken_steps, kind, resolved.trim());
+                    }
+                    if let [step] = matches.as_slice() {
+                        matched.get_or_insert(step);
+                        for (_, _, msg) in argument_errors(step, resolved.trim(), mode, &parsed.parameters) {
+                            if !arg_errors.contains(&msg) { arg_errors.push(msg); }
+                        }
+                    }
+                }
+                for msg in &arg_errors {
+                    diags.push(line_warning(i, &format!("{msg} (in Examples)")));
                 }
-                if !any_ok {
-                    diags.push(DiagnosticOut { line: i, message: "Undefined step (none of the Examples values match)".into(), severity: "warning" });
+                if let Some(step) = matched {
+                    for msg in step_arg_mismatches(step, supplied) {
+                        diags.push(line_warning(i, msg));
+                    }
+                }
+                if let (false, Some(msg)) = (any_ok, &broken) {
+                    diags.push(line_warning(i, msg));
+                } else if !any_ok {
+                    diags.push(line_warning(i, "Undefined step (none of the Examples values match)"));
                 } else if any_amb {
-                    diags.push(DiagnosticOut { line: i, message: "Ambiguous step (one or more Examples values have multiple matches)".into(), severity: "warning" });
+                    diags.push(line_warning(i, "Ambiguous step (one or more Examples values have multiple matches)"));
                 }
             } else {
                 let matches = match_one(&parsed.steps, kind, body, mode);
                 if matches.is_empty() {
-                    diags.push(DiagnosticOut { line: i, message: "Undefined step".into(), severity: "warning" });
+                    let msg = broken_definition(&broken_steps, kind, body.trim());
+                    diags.push(line_warning(i, msg.as_deref().unwrap_or("Undefined step")));
                 } else if matches.len() > 1 {
-                    diags.push(DiagnosticOut { line: i, message: "Ambiguous step".into(), severity: "warning" });
+                    diags.push(line_warning(i, "Ambiguous step"));
+                } else {
+                    for (start, end, message) in argument_errors(matches[0], body.trim(), mode, &parsed.parameters) {
+                        let col = |offset: usize| line[..body_start + offset].encode_utf16().count();
+                        diags.push(DiagnosticOut { line: i, message, severity: "warning", start_col: Some(col(start)), end_col: Some(col(end)) });
+                    }
+                    for msg in step_arg_mismatches(matches[0], supplied) {
+                        diags.push(line_warning(i, msg));
+                    }
                 }
             }
         }
@@ -167,12 +365,66 @@ pub fn diagnostics_for_feature(input_json: &str) -> String {
         .unwrap_or_else(|e| error_json(&format!("serde: {e}")))
 }
 
+/// A DataTable or DocString attached to a feature step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepArg {
+    Table,
+    DocString,
+}
+
+/// The argument following the step on line `i`, skipping blank and comment lines.
+fn supplied_step_arg(lines: &[&str], i: usize) -> Option<StepArg> {
+    let next = lines.iter().skip(i + 1).map(|l| l.trim()).find(|l| !l.is_empty() && !l.starts_with('#'))?;
+    if next.starts_with('|') {
+        Some(StepArg::Table)
+    } else if next.starts_with("\"\"\"") || next.starts_with("```") {
+        Some(StepArg::DocString)
+    } else {
+        None
+    }
+}
+
+/// Warnings for a DataTable/DocString the definition reads but the feature step lacks,
+/// or one the feature step supplies but the definition never reads.
+fn step_arg_mismatches(step: &core::StepEntry, supplied: Option<StepArg>) -> Vec<&'static str> {
+    let mut out = Vec::new();
+    if step.expects_table == Some(true) && supplied != Some(StepArg::Table) {
+        out.push("Step definition expects a DataTable");
+    }
+    if step.expects_docstring == Some(true) && supplied != Some(StepArg::DocString) {
+        out.push("Step definition expects a DocString");
+    }
+    if supplied == Some(StepArg::Table) && step.expects_table == Some(false) {
+        out.push("DataTable is never read by the step definition");
+    }
+    if supplied == Some(StepArg::DocString) && step.expects_docstring == Some(false) {
+        out.push("DocString is never read by the step definition");
+    }
+    out
+}
+
+/// Arguments `step` captures from `body` that do not parse into the type of the parameter
+/// receiving them, as (start, end) byte offsets in `body` and a message.
+fn argument_errors(step: &core::StepEntry, body: &str, mode: &str, custom: &[CustomParameter]) -> Vec<(usize, usize, String)> {
+    let Some(captures) = step.captures.as_deref() else { return Vec::new(); };
+    let Some(re) = pattern_for_step(step, mode).and_then(|p| Regex::new(&p).ok()) else { return Vec::new(); };
+    let Some(caps) = re.captures(body) else { return Vec::new(); };
+    captures
+        .iter()
+        .filter_map(|c| {
+            let m = caps.get(c.index)?;
+            let message = parameters::parse_error(m.as_str(), c.ty.as_deref()?, custom)?;
+            Some((m.start(), m.end(), message))
+        })
+        .collect()
+}
+
 fn match_one<'a>(steps: &'a [core::StepEntry], kind: core::StepKind, body: &str, mode: &str) -> Vec<&'a core::StepEntry> {
     let norm = body.trim();
     let mut out = Vec::new();
     for s in steps.iter() {
-        if s.kind != kind { continue; }
-        if let Some(p) = pattern_for_mode(&s.regex, mode) {
+        if s.kind != kind || s.invalid.is_some() { continue; }
+        if let Some(p) = pattern_for_step(s, mode) {
             if let Ok(re) = Regex::new(&p) {
                 if re.is_match(norm) { out.push(s); }
             }
@@ -181,6 +433,145 @@ fn match_one<'a>(steps: &'a [core::StepEntry], kind: core::StepKind, body: &str,
     out
 }
 
+/// A definition whose pattern does not compile, with why and the words its pattern
+/// requires literally (see [`literal_words`]).
+struct BrokenDefinition<'a> {
+    step: &'a core::StepEntry,
+    reason: String,
+    words: Vec<String>,
+}
+
+/// The definitions whose pattern does not compile, checked once per feature: the stored
+/// `invalid` reason, else the pattern is compiled (indexes written before it existed).
+fn broken_definitions(steps: &[core::StepEntry]) -> Vec<BrokenDefinition<'_>> {
+    steps
+        .iter()
+        .filter_map(|s| {
+            let reason = s.invalid.clone().or_else(|| s.pattern_error())?;
+            Some(BrokenDefinition { step: s, reason, words: literal_words(&s.regex, s.pattern_kind) })
+        })
+        .collect()
+}
+
+/// The diagnostic for an unmatched step `body` that can only have been meant for a
+/// definition whose pattern does not compile: every word the pattern requires literally
+/// appears in `body`, in order. `None` when no such definition exists.
+fn broken_definition(broken: &[BrokenDefinition], kind: core::StepKind, body: &str) -> Option<String> {
+    broken.iter().filter(|b| b.step.kind == kind).find_map(|b| {
+        let mut rest = body;
+        let meant = !b.words.is_empty()
+            && b.words.iter().all(|w| match rest.find(w.as_str()) {
+                Some(at) => {
+                    rest = &rest[at + w.len()..];
+                    true
+                }
+                None => false,
+            });
+        meant.then(|| format!("Step definition has {} ({}:{})", b.reason, b.step.file, b.step.line))
+    })
+}
+
+/// Words (alphanumeric runs) a pattern requires literally, in order. Escapes, classes,
+/// group syntax and repetition counts are skipped; optional characters and groups and
+/// alternations require nothing. Tolerates broken syntax.
+fn literal_words(pattern: &str, kind: core::PatternKind) -> Vec<String> {
+    match kind {
+        core::PatternKind::Literal => words_of(pattern),
+        core::PatternKind::Expression => {
+            // drop `{param}` and `(optional)` text, then alternatives (`a/b`)
+            let plain = Regex::new(r"\{[^}]*\}|\([^)]*\)").expect("valid regex").replace_all(pattern, " ");
+            plain.split_whitespace().filter(|w| !w.contains('/')).flat_map(words_of).collect()
+        }
+        core::PatternKind::Regex => regex_words(pattern),
+    }
+}
+
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+fn regex_words(pattern: &str) -> Vec<String> {
+    // one frame per open group: its required words and whether it has an alternation
+    let mut frames: Vec<(Vec<String>, bool)> = vec![(Vec::new(), false)];
+    let mut word = String::new();
+    let flush = |word: &mut String, frames: &mut Vec<(Vec<String>, bool)>| {
+        if !word.is_empty() {
+            frames.last_mut().expect("root frame").0.push(std::mem::take(word));
+        }
+    };
+    let optional = |next: Option<&char>| matches!(next, Some('?' | '*' | '{'));
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                flush(&mut word, &mut frames);
+                chars.next();
+            }
+            '[' | '{' => {
+                flush(&mut word, &mut frames);
+                let close = if c == '[' { ']' } else { '}' };
+                while let Some(n) = chars.next() {
+                    match n {
+                        '\\' => {
+                            chars.next();
+                        }
+                        n if n == close => break,
+                        _ => {}
+                    }
+                }
+            }
+            '(' => {
+                flush(&mut word, &mut frames);
+                frames.push((Vec::new(), false));
+                if chars.peek() == Some(&'?') {
+                    // group syntax: `(?:`, `(?i)`, `(?P<name>`
+                    for n in chars.by_ref() {
+                        match n {
+                            ':' | '>' => break,
+                            ')' => {
+                                frames.pop();
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            ')' => {
+                flush(&mut word, &mut frames);
+                if frames.len() > 1 {
+                    let (words, alternation) = frames.pop().expect("group frame");
+                    if !alternation && !optional(chars.peek()) {
+                        frames.last_mut().expect("root frame").0.extend(words);
+                    }
+                }
+            }
+            '|' => {
+                flush(&mut word, &mut frames);
+                frames.last_mut().expect("frame").1 = true;
+            }
+            c if c.is_alphanumeric() => {
+                if optional(chars.peek()) {
+                    flush(&mut word, &mut frames);
+                } else {
+                    word.push(c);
+                }
+            }
+            _ => flush(&mut word, &mut frames),
+        }
+    }
+    flush(&mut word, &mut frames);
+    // groups left open by a broken pattern count as written
+    while frames.len() > 1 {
+        let (words, alternation) = frames.pop().expect("group frame");
+        if !alternation {
+            frames.last_mut().expect("root frame").0.extend(words);
+        }
+    }
+    let (words, alternation) = frames.pop().expect("root frame");
+    if alternation { Vec::new() } else { words }
+}
+
 // -------- Dialect helpers (Phase 3) --------
 
 struct Dialect {
@@ -230,9 +621,9 @@ fn build_step_keyword_regex(d: &Dialect) -> Regex {
 }
 
 fn kind_from_keyword(kw: &str, d: &Dialect) -> Option<core::StepKind> {
-    if d.given.iter().any(|k| *k == kw) { return Some(core::StepKind::Given); }
-    if d.when.iter().any(|k| *k == kw) { return Some(core::StepKind::When); }
-    if d.then.iter().any(|k| *k == kw) { return Some(core::StepKind::Then); }
+    if d.given.contains(&kw) { return Some(core::StepKind::Given); }
+    if d.when.contains(&kw) { return Some(core::StepKind::When); }
+    if d.then.contains(&kw) { return Some(core::StepKind::Then); }
     None
 }
 
@@ -254,8 +645,8 @@ fn extract_outline_context<'a>(lines: &'a [&'a str], line_index: usize) -> Outli
     if !is_outline || start < 0 { return OutlineContext::default(); }
     // find nearest Examples
     let mut ex_start: isize = -1;
-    for i in (start as usize + 1)..lines.len() {
-        let t = lines[i].trim();
+    for (i, line) in lines.iter().enumerate().skip(start as usize + 1) {
+        let t = line.trim();
         if t.to_ascii_lowercase().starts_with("examples:") { ex_start = i as isize; break; }
         if t.to_ascii_lowercase().starts_with("scenario") { break; }
     }
@@ -286,7 +677,7 @@ fn extract_outline_context<'a>(lines: &'a [&'a str], line_index: usize) -> Outli
     OutlineContext { is_outline: true, examples: rows }
 }
 
-fn resolve_placeholders<'a>(body: &str, row: &std::collections::HashMap<&'a str, String>) -> String {
+fn resolve_placeholders(body: &str, row: &std::collections::HashMap<&str, String>) -> String {
     let re = Regex::new(r"<([^>]+)>").unwrap();
     re.replace_all(body, |caps: &regex::Captures| {
         let name = caps.get(1).map(|m| m.as_str()).unwrap_or("");
@@ -300,7 +691,31 @@ mod tests {
     use cukerust_core::step_index as core;
 
     fn step(kind: core::StepKind, regex: &str, file: &str, line: usize) -> core::StepEntry {
-        core::StepEntry { kind, regex: regex.into(), file: file.into(), line, function: None, captures: None, tags: None, notes: None }
+        core::StepEntry {
+            kind,
+            regex: regex.into(),
+            pattern_kind: core::PatternKind::Regex,
+            file: file.into(),
+            line,
+            function: None,
+            module: None,
+            captures: None,
+            tags: None,
+            notes: None,
+            extractor: None,
+            detector: None,
+            unresolved: None,
+            invalid: None,
+            span: None,
+            pattern_span: None,
+            function_span: None,
+            expects_table: None,
+            expects_docstring: None,
+        }
+    }
+
+    fn expr_step(kind: core::StepKind, expr: &str, file: &str, line: usize) -> core::StepEntry {
+        core::StepEntry { pattern_kind: core::PatternKind::Expression, ..step(kind, expr, file, line) }
     }
 
     #[test]
@@ -329,6 +744,35 @@ mod tests {
         assert!(m3.is_empty());
     }
 
+    #[test]
+    fn test_match_one_literal() {
+        let steps = vec![
+            core::StepEntry { pattern_kind: core::PatternKind::Literal, ..step(core::StepKind::Given, "a file (v1.0) exists?", "src/steps.rs", 10) },
+        ];
+        assert_eq!(match_one(&steps, core::StepKind::Given, "a file (v1.0) exists?", "smart").len(), 1);
+        assert!(match_one(&steps, core::StepKind::Given, "a file v1x0 exists", "smart").is_empty());
+        assert!(match_one(&steps, core::StepKind::Given, "so a file (v1.0) exists? now", "substring").is_empty());
+    }
+
+    #[test]
+    fn test_match_one_skips_unresolved() {
+        let steps = vec![core::StepEntry { unresolved: Some("unknown const `X`".into()), ..step(core::StepKind::Given, "X", "src/steps.rs", 10) }];
+        assert!(match_one(&steps, core::StepKind::Given, "X", "substring").is_empty());
+    }
+
+    #[test]
+    fn test_match_one_expression() {
+        let steps = vec![
+            expr_step(core::StepKind::Given, "I have {int} cuke(s)", "src/steps.rs", 10),
+            expr_step(core::StepKind::When, "I eat {word} in my belly/stomach", "src/steps.rs", 20),
+        ];
+        assert_eq!(match_one(&steps, core::StepKind::Given, "I have 1 cuke", "substring").len(), 1);
+        assert_eq!(match_one(&steps, core::StepKind::Given, "I have 5 cukes", "smart").len(), 1);
+        assert!(match_one(&steps, core::StepKind::Given, "I have five cukes", "smart").is_empty());
+        assert!(match_one(&steps, core::StepKind::Given, "so I have 5 cukes now", "substring").is_empty());
+        assert_eq!(match_one(&steps, core::StepKind::When, "I eat apples in my stomach", "anchored").len(), 1);
+    }
+
     #[test]
     fn test_diagnostics_basic_and_outline() {
         let steps = vec![
@@ -386,4 +830,123 @@ mod tests {
         assert!(msgs.iter().any(|m| m.contains("Ambiguous step")), "no ambiguous diag in {:?}", msgs);
         assert!(msgs.iter().any(|m| m.contains("Undefined step")), "no undefined diag in {:?}", msgs);
     }
+
+    #[test]
+    fn test_diagnostics_broken_definition() {
+        let mut broken = step(core::StepKind::Given, r"^I have (\d+ cukes in (?P<where>\w+)$", "src/steps.rs", 10);
+        broken.invalid = broken.pattern_error();
+        let steps = vec![
+            broken,
+            expr_step(core::StepKind::When, "I eat {int} cukes(", "src/steps.rs", 20),
+            step(core::StepKind::Then, r"^done$", "src/steps.rs", 30),
+        ];
+        let feature = r#"Feature: Sample
+  Scenario: Broken definitions
+    Given I have 5 cukes in the fridge
+    Given I have nothing
+    When I eat 3 cukes
+    Then done
+"#;
+        let input = serde_json::json!({
+            "feature_text": feature,
+            "config": { "dialect": "en", "match_mode": "smart" },
+            "steps": steps,
+        }).to_string();
+        let v: serde_json::Value = serde_json::from_str(&diagnostics_for_feature(&input)).unwrap();
+        let msgs: Vec<(u64, String)> = v["diags"].as_array().unwrap().iter().map(|d| (d["line"].as_u64().unwrap(), d["message"].as_str().unwrap().to_string())).collect();
+        assert_eq!(msgs.len(), 3, "{msgs:?}");
+        assert_eq!(msgs[0], (2, "Step definition has invalid regex: unclosed group at offset 8 (src/steps.rs:10)".to_string()));
+        assert_eq!(msgs[1], (3, "Undefined step".to_string()));
+        assert_eq!(msgs[2].0, 4);
+        assert!(msgs[2].1.starts_with("Step definition has invalid Cucumber Expression: "), "{msgs:?}");
+        assert!(msgs[2].1.ends_with("(src/steps.rs:20)"), "{msgs:?}");
+    }
+
+    #[test]
+    fn test_literal_words() {
+        assert_eq!(regex_words(r"^I (?:have|own) (\d+) red? apples?( today)?$"), vec!["I", "re", "apple"]);
+        assert_eq!(regex_words(r"^(?i)log (?P<who>in|out) as (\w+$"), vec!["log", "as"]);
+        assert!(regex_words(r"^a|b$").is_empty());
+        assert_eq!(literal_words("I eat {int} cukes(s) in/at the {word}", core::PatternKind::Expression), vec!["I", "eat", "cukes", "the"]);
+    }
+
+    #[test]
+    fn test_diagnostics_for_definitions() {
+        let input = serde_json::json!({
+            "files": [{
+                "path": "src/steps.rs",
+                "text": "#[then(regex = r\"^the index contains (\\d+) steps$\")]\nfn index_contains(world: &mut W, n: usize, extra: String) {}\n#[given(regex = r\"^ok (\\d+)$\")]\nfn ok(world: &mut W, n: usize) {}\n",
+            }],
+        }).to_string();
+        let v: serde_json::Value = serde_json::from_str(&diagnostics_for_definitions(&input)).unwrap();
+        let diags = v.get("diags").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+        assert_eq!(diags.len(), 1, "diags: {:?}", diags);
+        assert_eq!(diags[0]["file"], "src/steps.rs");
+        assert_eq!(diags[0]["line"], 0);
+        assert_eq!(diags[0]["severity"], "warning");
+        assert!(diags[0]["message"].as_str().unwrap_or("").contains("`index_contains` takes 2 step parameters"));
+    }
+
+    #[test]
+    fn test_diagnostics_typed_arguments() {
+        let typed = |regex: &str, ty: &str| core::StepEntry {
+            captures: Some(vec![core::Capture { index: 1, name: Some("n".into()), ty: Some(ty.into()), start: 0, end: 0 }]),
+            ..step(core::StepKind::Given, regex, "src/steps.rs", 1)
+        };
+        let steps = vec![typed(r"^I have (\w+) cukes$", "usize"), typed(r"^a pet (\w+)$", "Animal")];
+        let feature = "Feature: Sample\n  Scenario: Typed\n    Given I have five cukes\n    And I have 5 cukes\n    And a pet dög\n\n  Scenario Outline: Out\n    Given I have <n> cukes\n  Examples:\n    | n |\n    | 1 |\n    | x |\n";
+        let input = serde_json::json!({
+            "feature_text": feature,
+            "config": { "dialect": "en", "match_mode": "smart" },
+            "steps": steps,
+            "parameters": [{ "name": "animal", "type_name": "Animal", "regex": "cat|dog", "file": "src/animal.rs", "line": 1 }],
+        }).to_string();
+        let v: serde_json::Value = serde_json::from_str(&diagnostics_for_feature(&input)).unwrap();
+        let diags = v.get("diags").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+        let summary: Vec<_> = diags
+            .iter()
+            .map(|d| (d["line"].as_u64(), d["message"].as_str().unwrap_or("").to_string(), d["start_col"].as_u64(), d["end_col"].as_u64()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some(2), "argument 'five' cannot be parsed as usize".to_string(), Some(17), Some(21)),
+                (Some(4), "argument 'dög' cannot be parsed as Animal".to_string(), Some(14), Some(17)),
+                (Some(7), "argument 'x' cannot be parsed as usize (in Examples)".to_string(), None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_step_arguments() {
+        let with_args = |regex: &str, table: Option<bool>, docstring: Option<bool>| core::StepEntry {
+            expects_table: table,
+            expects_docstring: docstring,
+            ..step(core::StepKind::Given, regex, "src/steps.rs", 1)
+        };
+        let steps = vec![
+            with_args(r"^a table$", Some(true), Some(false)),
+            with_args(r"^a doc$", Some(false), Some(true)),
+            with_args(r"^plain$", Some(false), Some(false)),
+            with_args(r"^unknown$", None, None),
+        ];
+        let feature = "Feature: Sample\n  Scenario: Args\n    Given a table\n    And a doc\n      | x |\n    And plain\n      # comment\n      \"\"\"\n      text\n      \"\"\"\n    And unknown\n      | y |\n    And a table\n      | z |\n";
+        let input = serde_json::json!({
+            "feature_text": feature,
+            "config": { "dialect": "en", "match_mode": "smart" },
+            "steps": steps,
+        }).to_string();
+        let v: serde_json::Value = serde_json::from_str(&diagnostics_for_feature(&input)).unwrap();
+        let diags = v.get("diags").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+        let summary: Vec<_> = diags.iter().map(|d| (d["line"].as_u64().unwrap_or(0), d["message"].as_str().unwrap_or("").to_string())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, "Step definition expects a DataTable".to_string()),
+                (3, "Step definition expects a DocString".to_string()),
+                (3, "DataTable is never read by the step definition".to_string()),
+                (5, "DocString is never read by the step definition".to_string()),
+            ]
+        );
+    }
 }
@@ -0,0 +1,30 @@
+use cukerust_wasm::{load_step_index, match_steps};
+
+#[test]
+fn artifact_loads_best_effort() {
+    let artifact = serde_json::json!({
+        "steps": [
+            { "kind": "Given", "regex": "^I have (\\d+) cukes$", "file": "src/steps.rs", "line": 3 },
+            { "kind": "Gvien", "regex": "^typo$", "file": "src/steps.rs", "line": 7 }
+        ],
+        "stats": { "total": 2, "by_kind": { "Given": 2, "When": 0, "Then": 0 }, "ambiguous": 0 }
+    });
+    let v: serde_json::Value = serde_json::from_str(&load_step_index(&artifact.to_string())).expect("valid JSON");
+    assert_eq!(v["index"]["steps"].as_array().map(|a| a.len()), Some(1));
+    assert_eq!(v["index"]["stats"]["total"], 1);
+    assert_eq!(
+        v["rejected"],
+        serde_json::json!([{ "pointer": "/steps/1", "reason": "unknown variant `Gvien`, expected one of `Given`, `When`, `Then`" }])
+    );
+
+    // the same steps still match instead of failing the whole input
+    let input = serde_json::json!({
+        "steps": artifact["steps"],
+        "query": { "kind": "Given", "body": "I have 5 cukes" }
+    });
+    let v: serde_json::Value = serde_json::from_str(&match_steps(&input.to_string())).expect("valid JSON");
+    assert_eq!(v.as_array().map(|a| a.len()), Some(1), "{v}");
+
+    let v: serde_json::Value = serde_json::from_str(&load_step_index("[1, 2]")).expect("valid JSON");
+    assert_eq!(v["error"], "artifact: a Step Index artifact must be a JSON object");
+}
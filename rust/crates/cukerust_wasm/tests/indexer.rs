@@ -0,0 +1,36 @@
+use cukerust_wasm::StepIndexer;
+
+#[test]
+fn incremental_updates_report_deltas() {
+    let mut indexer = StepIndexer::new(r#"{ "detectors": { "macros": [{ "name": "step" }] } }"#).expect("valid config");
+    let files = serde_json::json!({
+        "files": [
+            { "path": "src/a.rs", "text": "given!(r\"^a$\", f);" },
+            { "path": "src/b.rs", "text": "step!(When, r\"^b$\", f);" }
+        ]
+    });
+    let delta: serde_json::Value = serde_json::from_str(&indexer.upsert_files(&files.to_string())).expect("valid JSON");
+    assert_eq!(delta["added"].as_array().map(Vec::len), Some(2));
+    assert_eq!(delta["stats"]["total"], 2);
+
+    let file = serde_json::json!({ "path": "src/a.rs", "text": "\ngiven!(r\"^a$\", f);" });
+    let delta: serde_json::Value = serde_json::from_str(&indexer.upsert_file(&file.to_string())).expect("valid JSON");
+    assert_eq!(delta["changed"][0]["line"], 2);
+    assert_eq!(delta["added"].as_array().map(Vec::len), Some(0));
+
+    let delta: serde_json::Value = serde_json::from_str(&indexer.remove_file("src/b.rs")).expect("valid JSON");
+    assert_eq!(delta["removed"][0]["regex"], "^b$");
+    assert_eq!(delta["stats"]["by_kind"]["When"], 0);
+
+    let steps: serde_json::Value = serde_json::from_str(&indexer.file_steps("src/a.rs")).expect("valid JSON");
+    assert_eq!(steps.as_array().map(Vec::len), Some(1));
+    let snapshot: serde_json::Value = serde_json::from_str(&indexer.snapshot()).expect("valid JSON");
+    assert_eq!(snapshot["stats"]["total"], 1);
+
+    assert_eq!(indexer.warnings(), "[]");
+    assert_eq!(indexer.parameters(), "[]");
+
+    let out: serde_json::Value = serde_json::from_str(&indexer.upsert_file("not json")).expect("valid JSON");
+    assert!(out.get("error").is_some());
+    assert!(StepIndexer::new("not json").is_err());
+}
@@ -1,4 +1,4 @@
-use cukerust_wasm::extract_step_index;
+use cukerust_wasm::{diagnostics_for_definitions, extract_step_index};
 #[test]
 fn json_round_trip() {
     let input = serde_json::json!({
@@ -14,3 +14,38 @@ fn json_round_trip() {
     let v: serde_json::Value = serde_json::from_str(&out).expect("valid JSON");
     assert!(v.get("steps").and_then(|s| s.as_array()).map(|a| a.len()).unwrap_or(0) >= 3);
 }
+
+#[test]
+fn extraction_warnings_in_json() {
+    let input = serde_json::json!({
+        "files": [
+            { "path": "src/steps.rs", "text": "when!(r\"^eat$\", || {});\ngiven!(pattern(), || {});\n" }
+        ]
+    })
+    .to_string();
+
+    let v: serde_json::Value = serde_json::from_str(&extract_step_index(&input)).expect("valid JSON");
+    assert_eq!(v["steps"].as_array().map(|a| a.len()), Some(1));
+    assert_eq!(
+        v["warnings"],
+        serde_json::json!([{
+            "kind": "UnsupportedPattern",
+            "file": "src/steps.rs",
+            "line": 2,
+            "detector": "Macro",
+            "message": "`given!` has no string literal, const or `concat!` pattern; the step is not indexed"
+        }])
+    );
+    let diags: serde_json::Value = serde_json::from_str(&diagnostics_for_definitions(&input)).expect("valid JSON");
+    assert_eq!(diags["diags"][0]["line"], 1);
+}
+
+#[test]
+fn overlaps_on_request() {
+    let files = serde_json::json!([{ "path": "src/steps.rs", "text": "given!(r\"^I have (\\d+) cukes$\", f);\ngiven!(r\"^I have (.*) cukes$\", g);\n" }]);
+    let plain: serde_json::Value = serde_json::from_str(&extract_step_index(&serde_json::json!({ "files": files }).to_string())).expect("valid JSON");
+    assert!(plain["stats"].get("overlaps").is_none());
+    let input = serde_json::json!({ "files": files, "overlaps": true }).to_string();
+    let v: serde_json::Value = serde_json::from_str(&extract_step_index(&input)).expect("valid JSON");
+    assert_eq!(v["stats"]["overlaps"][0]["witness"], "I have 0 cukes");
+}
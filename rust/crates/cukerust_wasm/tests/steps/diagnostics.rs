@@ -0,0 +1,60 @@
+use cucumber::gherkin::Step;
+use cucumber::{given, then, when};
+use cukerust_core::step_index::SourceFile;
+use cukerust_wasm::{diagnostics_for_feature, extract_step_index};
+
+use crate::WasmWorld;
+
+#[given(regex = r#"^a Rust file \"([^\"]+)\" with content:$"#)]
+async fn a_rust_file_with_content(world: &mut WasmWorld, path: String, step: &Step) {
+    let text = step.docstring.clone().unwrap_or_default();
+    world.files.push(SourceFile { path, text });
+}
+
+#[given("a feature file with content:")]
+async fn a_feature_file_with_content(world: &mut WasmWorld, step: &Step) {
+    // the docstring starts with a newline; drop it so feature lines number from 1
+    let text = step.docstring.clone().unwrap_or_default();
+    world.feature_text = text.strip_prefix('\n').unwrap_or(&text).to_string();
+}
+
+// Runs the same JSON round trip as the extension: extract, then diagnose with the steps found
+#[when("we compute diagnostics for the feature")]
+async fn we_compute_diagnostics(world: &mut WasmWorld) {
+    let input = serde_json::json!({ "files": world.files }).to_string();
+    let index: serde_json::Value = serde_json::from_str(&extract_step_index(&input)).expect("valid JSON");
+    let input = serde_json::json!({
+        "feature_text": world.feature_text,
+        "config": { "dialect": "auto", "matchMode": "smart" },
+        "steps": index["steps"],
+        "parameters": index.get("parameters").cloned().unwrap_or(serde_json::json!([])),
+    })
+    .to_string();
+    let out: serde_json::Value = serde_json::from_str(&diagnostics_for_feature(&input)).expect("valid JSON");
+    world.diags = out["diags"].as_array().cloned().unwrap_or_else(|| panic!("diagnostics list: {out}"));
+}
+
+#[then("there are no diagnostics")]
+async fn there_are_no_diagnostics(world: &mut WasmWorld) {
+    assert!(world.diags.is_empty(), "unexpected diagnostics: {:?}", world.diags);
+}
+
+#[then(regex = r#"^there is a diagnostic on line (\d+): \"(.*)\"$"#)]
+async fn there_is_a_diagnostic(world: &mut WasmWorld, line: u64, message: String) {
+    assert!(
+        world.diags.iter().any(|d| d["line"].as_u64() == Some(line - 1) && d["message"] == message.as_str()),
+        "no diagnostic {message:?} on line {line}: {:?}",
+        world.diags
+    );
+}
+
+#[then(regex = r#"^the diagnostic \"(.*)\" spans columns (\d+) to (\d+)$"#)]
+async fn diagnostic_spans_columns(world: &mut WasmWorld, message: String, start: u64, end: u64) {
+    let d = world.diags.iter().find(|d| d["message"] == message.as_str()).expect("diagnostic");
+    assert_eq!((d["start_col"].as_u64(), d["end_col"].as_u64()), (Some(start), Some(end)));
+}
+
+#[then(regex = r"^there are (\d+) diagnostics$")]
+async fn there_are_n_diagnostics(world: &mut WasmWorld, n: usize) {
+    assert_eq!(world.diags.len(), n, "diagnostics: {:?}", world.diags);
+}